@@ -0,0 +1,508 @@
+use super::{Checker, Error, Warning};
+use super::super::parser::{Ident, MatchSource};
+use super::super::referencer::{Expression, Pattern, Type, TypeItemKind};
+
+/// A match waiting for its types to be solved before it is checked.
+pub struct Match<'a> {
+    pub function: usize,
+    pub on: Type,
+    pub variants: &'a [(Pattern, Expression)],
+    pub source: MatchSource,
+}
+
+#[derive(Clone, PartialEq)]
+enum Constructor {
+    Tag(Ident),
+    Fields,
+    Tuple,
+    Number(u64),
+}
+
+/// A pattern reduced to what matters for coverage. Number literals are
+/// constructors of their number type; other equality tests and guards
+/// cover no value for sure, so they are `Opaque`.
+#[derive(Clone)]
+enum Row {
+    Any,
+    Constructor(Constructor, Vec<Row>),
+    Opaque,
+    Or(Vec<Row>),
+}
+
+/// A value no pattern matches, with `Any` standing for whatever is left.
+enum Example {
+    Any,
+    Constructor(Constructor, Vec<Example>),
+}
+
+/// The ways of building a value of one type, each with the types of what it holds.
+struct Shape {
+    constructors: Vec<(Constructor, Vec<Type>)>,
+    is_sum: bool,
+}
+
+impl Checker<'_> {
+    /// Reports the first value `value` does not cover, and every variant
+    /// that the ones before it already cover. A guarded variant may still
+    /// fail, so it covers nothing for the ones after it.
+    pub(super) fn exhaustive(&self, value: &Match, warnings: &mut Vec<Warning>) -> Result<(), Error> {
+        let types = [value.on.clone()];
+        let mut rows = Vec::new();
+
+        for (index, (pattern, _)) in value.variants.iter().enumerate() {
+            let (pattern, is_guarded) = match pattern {
+                Pattern::Guard { pattern, .. } => (&**pattern, true),
+                pattern => (pattern, false),
+            };
+            let row = vec![self.row(pattern, &value.on)];
+            let is_fallback = value.source == MatchSource::If { fallback: true } && index == value.variants.len() - 1;
+            if !is_fallback && !self.useful(&rows, &row, &types) {
+                warnings.push(Warning::UnreachableVariant { function: value.function, index });
+            }
+            if !is_guarded {
+                rows.push(row);
+            }
+        }
+
+        let Some(example) = self.witness(&rows, &types) else {
+            return Ok(())
+        };
+        let function = value.function;
+        let example = self.example(&example[0], &value.on);
+        match value.source {
+            MatchSource::Let => Err(Error::Refutable { function, example }),
+            MatchSource::Match | MatchSource::If { .. } => Err(Error::NonExhaustive { function, example }),
+        }
+    }
+
+    fn shape(&self, type_: &Type) -> Shape {
+        let (members, is_sum) = match self.resolve(type_) {
+            Type::Item(id, _) => match &self.program.types[id].kind {
+                TypeItemKind::Struct(_) => (self.fields(type_).unwrap_or_default(), false),
+                TypeItemKind::Enum(_) => (self.variants(type_).unwrap_or_default(), true),
+                TypeItemKind::Alias(_) => unreachable!("aliases are expanded where they are named"),
+            },
+            Type::Record(members) => (members.iter().cloned().collect(), false),
+            Type::Variant(members) => (members.iter().cloned().collect(), true),
+            Type::Tuple(items) => return Shape {
+                constructors: vec![(Constructor::Tuple, items.to_vec())],
+                is_sum: false,
+            },
+            _ => return Shape { constructors: Vec::new(), is_sum: false },
+        };
+
+        let mut members: Vec<(Ident, Type)> = members.into_iter().collect();
+        members.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+        let constructors = match is_sum {
+            true => members.into_iter().map(|(tag, type_)| (Constructor::Tag(tag), vec![type_])).collect(),
+            false => vec![(Constructor::Fields, members.into_iter().map(|(_, type_)| type_).collect())],
+        };
+        Shape { constructors, is_sum }
+    }
+
+    /// The shape of `type_` as far as a column starting with `named` needs
+    /// it. A number type has a constructor per number, so only the named
+    /// ones are listed, and the first one not named if there is one.
+    fn column_shape(&self, type_: &Type, named: &[&Constructor]) -> Shape {
+        let Type::Number(number) = self.resolve(type_) else {
+            return self.shape(type_)
+        };
+        let mut numbers: Vec<u64> = named.iter()
+            .filter_map(|constructor| match constructor {
+                Constructor::Number(value) => Some(*value),
+                _ => None,
+            })
+            .collect();
+        numbers.sort_unstable();
+        numbers.dedup();
+
+        let missing = (0..number).find(|value| numbers.binary_search(value).is_err());
+        let constructors = numbers.into_iter().chain(missing).map(|value| (Constructor::Number(value), Vec::new())).collect();
+        Shape { constructors, is_sum: true }
+    }
+
+    /// The names of the members of a struct or record, in the order `shape` lists their types.
+    fn field_names(&self, type_: &Type) -> Vec<Ident> {
+        let mut names: Vec<Ident> = match self.resolve(type_) {
+            Type::Record(members) => members.iter().map(|(name, _)| name.clone()).collect(),
+            _ => self.fields(type_).unwrap_or_default().into_keys().collect(),
+        };
+        names.sort();
+        names
+    }
+
+    fn row(&self, value: &Pattern, type_: &Type) -> Row {
+        match value {
+            Pattern::Any(_) | Pattern::Wildcard => Row::Any,
+            Pattern::Equals(Expression::Number { value, .. }) => Row::Constructor(Constructor::Number(*value), Vec::new()),
+            Pattern::Equals(_) | Pattern::Guard { .. } => Row::Opaque,
+            Pattern::Bind(_, pattern) => self.row(pattern, type_),
+            Pattern::Or(alternatives) => Row::Or(alternatives.iter().map(|alternative| self.row(alternative, type_)).collect()),
+            Pattern::Tag { body, mul: Some(_), .. } if !self.shape(type_).is_sum => self.row(body, type_),
+            Pattern::Tag { tag, body, .. } => {
+                let variant = self.variant(type_, tag).expect("checked tag pattern");
+                Row::Constructor(Constructor::Tag(tag.clone()), vec![self.row(body, &variant)])
+            },
+            Pattern::Fields(fields) => {
+                let Shape { constructors, .. } = self.shape(type_);
+                let types = &constructors[0].1;
+                let rows = self.field_names(type_).iter().zip(types)
+                    .map(|(name, type_)| match fields.get(name) {
+                        Some(field) => self.row(field, type_),
+                        None => Row::Any,
+                    })
+                    .collect();
+                Row::Constructor(Constructor::Fields, rows)
+            },
+            Pattern::Tuple(items) => {
+                let Shape { constructors, .. } = self.shape(type_);
+                let rows = items.iter().zip(&constructors[0].1)
+                    .map(|(item, type_)| self.row(item, type_))
+                    .collect();
+                Row::Constructor(Constructor::Tuple, rows)
+            },
+        }
+    }
+
+    /// Whether some value matched by `row` is matched by none of `rows`.
+    fn useful(&self, rows: &[Vec<Row>], row: &[Row], types: &[Type]) -> bool {
+        let Some((head, rest)) = row.split_first() else {
+            return rows.is_empty()
+        };
+        let rows = &alternatives(rows);
+        let mut named = heads(rows);
+        if let Row::Constructor(constructor, _) = head {
+            named.push(constructor);
+        }
+        let shape = self.column_shape(&types[0], &named);
+
+        match head {
+            Row::Or(items) => items.iter().any(|item| {
+                let row: Vec<Row> = [item.clone()].into_iter().chain(rest.iter().cloned()).collect();
+                self.useful(rows, &row, types)
+            }),
+            Row::Constructor(constructor, items) => {
+                let (_, members) = shape.constructors.iter()
+                    .find(|(other, _)| other == constructor)
+                    .expect("checked constructor");
+                let row: Vec<Row> = items.iter().chain(rest).cloned().collect();
+                let types: Vec<Type> = members.iter().chain(&types[1..]).cloned().collect();
+                self.useful(&specialize(rows, constructor, members.len()), &row, &types)
+            },
+            Row::Any | Row::Opaque => match is_complete(rows, &shape) {
+                true => shape.constructors.iter().any(|(constructor, members)| {
+                    let row: Vec<Row> = members.iter().map(|_| Row::Any).chain(rest.iter().cloned()).collect();
+                    let types: Vec<Type> = members.iter().chain(&types[1..]).cloned().collect();
+                    self.useful(&specialize(rows, constructor, members.len()), &row, &types)
+                }),
+                false => self.useful(&default(rows), rest, &types[1..]),
+            },
+        }
+    }
+
+    /// Values of `types` that none of `rows` match, if there are any.
+    fn witness(&self, rows: &[Vec<Row>], types: &[Type]) -> Option<Vec<Example>> {
+        let Some((type_, rest)) = types.split_first() else {
+            return rows.is_empty().then(Vec::new)
+        };
+        let rows = &alternatives(rows);
+        let shape = self.column_shape(type_, &heads(rows));
+
+        if is_complete(rows, &shape) {
+            return shape.constructors.iter().find_map(|(constructor, members)| {
+                let types: Vec<Type> = members.iter().chain(rest).cloned().collect();
+                let mut examples = self.witness(&specialize(rows, constructor, members.len()), &types)?;
+                let items = examples.drain(..members.len()).collect();
+                examples.insert(0, Example::Constructor(constructor.clone(), items));
+                Some(examples)
+            });
+        }
+
+        let mut examples = self.witness(&default(rows), rest)?;
+        let missing = shape.constructors.iter().find(|(constructor, _)| !heads(rows).contains(&constructor));
+        let head = match missing {
+            Some((constructor, members)) if shape.is_sum => {
+                Example::Constructor(constructor.clone(), members.iter().map(|_| Example::Any).collect())
+            },
+            _ => Example::Any,
+        };
+        examples.insert(0, head);
+        Some(examples)
+    }
+
+    /// Writes `value` the way a value of `type_` is written in source.
+    fn example(&self, value: &Example, type_: &Type) -> String {
+        let resolved = self.resolve(type_);
+        let (constructor, items) = match value {
+            Example::Any if resolved == Type::Tuple(Box::default()) => return "()".into(),
+            Example::Any => return "_".into(),
+            Example::Constructor(constructor, items) => (constructor, items),
+        };
+        let Shape { constructors, .. } = self.column_shape(type_, &[constructor]);
+        let (_, members) = constructors.iter()
+            .find(|(other, _)| other == constructor)
+            .expect("examples are built from the shape of their type");
+        let items: Vec<String> = items.iter().zip(members).map(|(item, type_)| self.example(item, type_)).collect();
+
+        match (constructor, &resolved) {
+            (Constructor::Tag(tag), Type::Item(id, _)) => {
+                format!("{}:{} {}", self.program.types[*id].name.to_pascal_case(), tag.to_pascal_case(), items[0])
+            },
+            (Constructor::Tag(tag), _) => format!("`{tag} {}", items[0]),
+            (Constructor::Fields, _) => {
+                let (name, case): (_, fn(&Ident) -> String) = match resolved {
+                    Type::Item(id, _) => (format!("{} ", self.program.types[id].name.to_pascal_case()), Ident::to_pascal_case),
+                    _ => (String::new(), Ident::to_string),
+                };
+                let fields: Vec<String> = self.field_names(type_).iter().zip(items)
+                    .map(|(field, item)| format!("{} = {item}", case(field)))
+                    .collect();
+                match fields.is_empty() {
+                    true => format!("{name}{{}}"),
+                    false => format!("{name}{{ {} }}", fields.join(", ")),
+                }
+            },
+            (Constructor::Tuple, _) if items.len() == 1 => format!("({},)", items[0]),
+            (Constructor::Tuple, _) => format!("({})", items.join(", ")),
+            (Constructor::Number(value), _) => value.to_string(),
+        }
+    }
+}
+
+/// The constructors the first column of `rows` starts with.
+fn heads(rows: &[Vec<Row>]) -> Vec<&Constructor> {
+    rows.iter()
+        .filter_map(|row| match &row[0] {
+            Row::Constructor(constructor, _) => Some(constructor),
+            Row::Any | Row::Opaque => None,
+            Row::Or(_) => unreachable!("alternatives are split into rows first"),
+        })
+        .collect()
+}
+
+/// `rows` with every row starting with an or-pattern split into one row per
+/// alternative.
+fn alternatives(rows: &[Vec<Row>]) -> Vec<Vec<Row>> {
+    let mut split = Vec::new();
+    for row in rows {
+        match &row[0] {
+            Row::Or(items) => split.extend(alternatives(&items.iter()
+                .map(|item| [item.clone()].into_iter().chain(row[1..].iter().cloned()).collect())
+                .collect::<Vec<_>>())),
+            _ => split.push(row.clone()),
+        }
+    }
+    split
+}
+
+/// Whether the first column of `rows` names every constructor of `shape`,
+/// so that a wildcard there needs checking against each of them.
+fn is_complete(rows: &[Vec<Row>], shape: &Shape) -> bool {
+    let heads = heads(rows);
+    !shape.constructors.is_empty() && shape.constructors.iter().all(|(constructor, _)| heads.contains(&constructor))
+}
+
+/// The rows that may match a value built with `constructor`, with its
+/// `len` members in place of their first column.
+fn specialize(rows: &[Vec<Row>], constructor: &Constructor, len: usize) -> Vec<Vec<Row>> {
+    rows.iter()
+        .filter_map(|row| {
+            let items = match &row[0] {
+                Row::Any => vec![Row::Any; len],
+                Row::Constructor(other, items) if other == constructor => items.clone(),
+                Row::Constructor(..) | Row::Opaque => return None,
+                Row::Or(_) => unreachable!("alternatives are split into rows first"),
+            };
+            Some(items.into_iter().chain(row[1..].iter().cloned()).collect())
+        })
+        .collect()
+}
+
+/// The rows matching anything in their first column, without it.
+fn default(rows: &[Vec<Row>]) -> Vec<Vec<Row>> {
+    rows.iter()
+        .filter(|row| matches!(row[0], Row::Any))
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{checker, loader, referencer};
+
+    /// Checks `source`, giving back its warnings or its first error as
+    /// the driver prints them.
+    fn check(source: &str) -> Result<Vec<String>, String> {
+        let (root, _) = loader::source("test.sn", source).map_err(|error| error.to_string())?;
+        let mut program = referencer::resolve(&root).map_err(|error| error.error.to_string())?;
+        let table = checker::check(&mut program).map_err(|error| program.named(&error).to_string())?;
+        Ok(table.warnings.iter().map(|warning| program.named(warning).to_string()).collect())
+    }
+
+    const FRUIT: &str = "
+        sum Bool { True, False }
+        sum Fruit { Apple, Banana, Cherry: Bool }
+    ";
+
+    #[test]
+    fn missing_tag() {
+        let source = format!("{FRUIT}
+            fn start () -> () a {{
+                match Fruit:Apple () {{ Apple _ -> (), Cherry _ -> () }}
+            }}
+        ");
+        assert_eq!(check(&source), Err("a match in `start` does not cover `Fruit:Banana ()`".into()));
+    }
+
+    #[test]
+    fn missing_nested_tag() {
+        let source = format!("{FRUIT}
+            fn start () -> () a {{
+                match Fruit:Apple () {{ Apple _ | Banana _ -> (), Cherry True _ -> () }}
+            }}
+        ");
+        assert_eq!(check(&source), Err("a match in `start` does not cover `Fruit:Cherry Bool:False ()`".into()));
+    }
+
+    #[test]
+    fn missing_in_tuple() {
+        let source = format!("{FRUIT}
+            fn start () -> () a {{
+                match (Bool:True (), Bool:True ()) {{ (True _, _) -> (), (_, True _) -> () }}
+            }}
+        ");
+        assert_eq!(check(&source), Err("a match in `start` does not cover `(Bool:False (), Bool:False ())`".into()));
+    }
+
+    #[test]
+    fn missing_in_fields_lists_them_by_name() {
+        let source = format!("{FRUIT}
+            mul Basket {{ Right: Bool, Left: Fruit }}
+            fn start () -> () a {{
+                let b = Basket {{ Left = Fruit:Apple (), Right = Bool:True () }};
+                match b {{ {{ Left = Apple _ }} -> (), {{ Right = False _ }} -> (), {{ Left = Banana _ }} -> () }}
+            }}
+        ");
+        assert_eq!(
+            check(&source),
+            Err("a match in `start` does not cover `Basket { Left = Fruit:Cherry _, Right = Bool:True () }`".into()),
+        );
+    }
+
+    #[test]
+    fn missing_number() {
+        let source = "
+            fn start () -> () a {
+                match (2, 1) { (0, _) -> (), (1, _) -> (), (2, 0) -> () }
+            }
+        ";
+        assert_eq!(check(source), Err("a match in `start` does not cover `(2, 1)`".into()));
+    }
+
+    #[test]
+    fn numbers_cover_their_type() {
+        let source = "
+            fn f 3 -> () n { match n { 0 -> (), 1 -> (), 2 -> () } }
+            fn g 3 -> () n { match n { 0 -> (), 1 | 2 -> (), _ -> () } }
+            fn start () -> () a { () }
+        ";
+        assert_eq!(check(source), Ok(vec!["variant 3 of a match in `g` is unreachable".into()]));
+    }
+
+    #[test]
+    fn large_number_types_need_a_wildcard() {
+        let source = "
+            fn f 4000000000 -> () n { match n { 0 -> (), ~1 -> () } }
+            fn start () -> () a { () }
+        ";
+        assert_eq!(check(source), Err("a match in `f` does not cover `2`".into()));
+    }
+
+    #[test]
+    fn guard_covers_nothing() {
+        let source = format!("{FRUIT}
+            fn start () -> () a {{
+                match Bool:True () {{ True _ -> (), b if b -> () }}
+            }}
+        ");
+        assert_eq!(check(&source), Err("a match in `start` does not cover `Bool:False ()`".into()));
+    }
+
+    #[test]
+    fn alternatives_cover() {
+        let source = format!("{FRUIT}
+            fn start () -> () a {{
+                match Fruit:Apple () {{ Apple _ | Cherry True _ -> (), Banana _ | Cherry False _ -> () }}
+            }}
+        ");
+        assert_eq!(check(&source), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn unreachable_variants() {
+        let source = format!("{FRUIT}
+            fn start () -> () a {{
+                match Fruit:Apple () {{ Cherry _ -> (), Apple _ -> (), Cherry True _ -> (), _ -> (), Banana _ -> () }}
+            }}
+        ");
+        assert_eq!(check(&source), Ok(vec![
+            "variant 3 of a match in `start` is unreachable".into(),
+            "variant 5 of a match in `start` is unreachable".into(),
+        ]));
+    }
+
+    #[test]
+    fn if_without_else_is_not_unreachable() {
+        let source = format!("{FRUIT}
+            fn start () -> () a {{
+                if Cherry c = Fruit:Apple () {{ () }}
+            }}
+        ");
+        assert_eq!(check(&source), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn refutable_let() {
+        let source = format!("{FRUIT}
+            fn start () -> () a {{
+                let Cherry b = Fruit:Apple ();
+            }}
+        ");
+        assert_eq!(check(&source), Err("a let in `start` does not match `Fruit:Apple ()`, so it needs an `else`".into()));
+    }
+
+    #[test]
+    fn refutable_let_with_else() {
+        let source = format!("{FRUIT}
+            fn start () -> () a {{
+                let Cherry b = Fruit:Apple () else ();
+            }}
+        ");
+        assert_eq!(check(&source), Ok(vec![]));
+    }
+
+    #[test]
+    fn let_else_has_the_type_of_its_block() {
+        let source = format!("{FRUIT}
+            fn f Fruit -> Bool x {{
+                let Cherry b = x else ();
+                b
+            }}
+            fn start () -> () a {{ () }}
+        ");
+        assert_eq!(check(&source), Err("in `f`: expected `Bool`, found `()`".into()));
+    }
+
+    #[test]
+    fn single_tag_let() {
+        let source = format!("{FRUIT}
+            sum Only {{ Only: Bool }}
+            fn start () -> () a {{
+                let Only b = Only:Only Bool:True ();
+                let (Only c, _) = (Only:Only b, Fruit:Banana ());
+            }}
+        ");
+        assert_eq!(check(&source), Ok(vec![]));
+    }
+}
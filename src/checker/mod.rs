@@ -0,0 +1,1322 @@
+use std::{collections::{HashMap, HashSet}, fmt};
+use super::parser::Ident;
+use super::referencer::{canonical, Program, Named, Expression, Pattern, Statement, Type, TypeItemKind};
+
+mod exhaustiveness;
+
+#[derive(Debug)]
+pub enum Error {
+    Mismatch {
+        expected: Type,
+        found: Type,
+    },
+    StructExpected(Type),
+    EnumExpected(Type),
+    UnknownField {
+        type_: Type,
+        name: Ident,
+    },
+    MissingField {
+        type_: Type,
+        name: Ident,
+    },
+    UnknownTag {
+        type_: Type,
+        tag: Ident,
+    },
+    EmptyMatch,
+    /// A number literal that is not one of the values of `type_`.
+    Number {
+        value: u64,
+        type_: Type,
+    },
+    /// A type that nothing settled, or that `candidates` disagree on.
+    Hole {
+        candidates: Vec<Type>,
+    },
+    /// A match in `function` lets `example` through every variant.
+    NonExhaustive {
+        function: usize,
+        example: String,
+    },
+    /// A `let` in `function` without `else` does not match `example`.
+    Refutable {
+        function: usize,
+        example: String,
+    },
+    /// The member `member` of the declaration `type_` holds `mentioned`, a
+    /// declaration recursive with it, applied to anything other than bare
+    /// type parameters, so the two have no finite set of layouts.
+    NonRegular {
+        type_: usize,
+        member: Ident,
+        mentioned: usize,
+    },
+    /// The standalone signature of `function` says it is `signature`, but
+    /// its definition makes it `found`.
+    Signature {
+        function: usize,
+        signature: Type,
+        found: Type,
+    },
+    /// `error` was found while checking `function`.
+    In {
+        function: usize,
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    fn in_function(self, function: usize) -> Self {
+        match self {
+            Self::NonExhaustive { .. } | Self::Refutable { .. } | Self::Signature { .. } | Self::In { .. } => self,
+            error => Self::In { function, error: Box::new(error) },
+        }
+    }
+
+    /// The function the error was found in, if it belongs to one.
+    pub fn function(&self) -> Option<usize> {
+        match self {
+            Self::NonExhaustive { function, .. }
+            | Self::Refutable { function, .. }
+            | Self::Signature { function, .. }
+            | Self::In { function, .. } => Some(*function),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Warning {
+    /// The variant at `index` of a match in `function` only matches values
+    /// that the variants before it already do.
+    UnreachableVariant {
+        function: usize,
+        index: usize,
+    },
+}
+
+impl fmt::Display for Named<'_, Error> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let named = |type_| self.program.named(type_);
+        let function = |id: &usize| &self.program.functions[*id].name;
+        let member = |type_: &Type, name: &Ident| match type_.is_declared() {
+            true => name.to_pascal_case(),
+            false => name.to_string(),
+        };
+
+        match self.value {
+            Error::Mismatch { expected, found } => write!(f, "expected `{}`, found `{}`", named(expected), named(found)),
+            Error::StructExpected(type_) => write!(f, "expected a mul or record, found `{}`", named(type_)),
+            Error::EnumExpected(type_) => write!(f, "expected a sum or variant, found `{}`", named(type_)),
+            Error::UnknownField { type_, name } => write!(f, "`{}` has no field `{}`", named(type_), member(type_, name)),
+            Error::MissingField { type_, name } => write!(f, "`{}` is missing the field `{}`", named(type_), member(type_, name)),
+            Error::UnknownTag { type_, tag } => write!(f, "`{}` has no variant `{}`", named(type_), member(type_, tag)),
+            Error::EmptyMatch => write!(f, "a match needs at least one variant"),
+            Error::Number { value, type_ } => write!(f, "`{value}` is not a value of `{}`", named(type_)),
+            Error::Hole { candidates } => {
+                write!(f, "cannot infer a type")?;
+                for (index, candidate) in candidates.iter().enumerate() {
+                    match index {
+                        0 => write!(f, ", it could be `{}`", named(candidate))?,
+                        _ => write!(f, " or `{}`", named(candidate))?,
+                    }
+                }
+                Ok(())
+            },
+            Error::NonExhaustive { function: id, example } => {
+                write!(f, "a match in `{}` does not cover `{example}`", function(id))
+            },
+            Error::Refutable { function: id, example } => {
+                write!(f, "a let in `{}` does not match `{example}`, so it needs an `else`", function(id))
+            },
+            Error::NonRegular { type_, member, mentioned } => write!(f,
+                "the member `{}` of `{}` holds `{}` with type arguments other than type parameters, \
+                which would give it infinitely many layouts",
+                member.to_pascal_case(),
+                self.program.types[*type_].name.to_pascal_case(),
+                self.program.types[*mentioned].name.to_pascal_case(),
+            ),
+            Error::Signature { function: id, signature, found } => write!(f,
+                "the signature `{name} :: {}` does not match `fn {name}`, which is `{}`",
+                named(signature), named(found), name = function(id),
+            ),
+            Error::In { function: id, error } => write!(f, "in `{}`: {}", function(id), self.program.named(&**error)),
+        }
+    }
+}
+
+impl Warning {
+    /// The function the warning was found in.
+    pub fn function(&self) -> usize {
+        match self {
+            Self::UnreachableVariant { function, .. } => *function,
+        }
+    }
+}
+
+impl fmt::Display for Named<'_, Warning> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Warning::UnreachableVariant { function, index } => {
+                let function = &self.program.functions[*function].name;
+                write!(f, "variant {} of a match in `{function}` is unreachable", index + 1)
+            },
+        }
+    }
+}
+
+pub struct Table {
+    pub nodes: Vec<Type>,
+    pub locals: Vec<Vec<Type>>,
+    pub schemes: Vec<Box<[usize]>>,
+    pub instances: HashMap<usize, Box<[Type]>>,
+    /// For each type declaration, the members whose type leads back to the
+    /// declaration itself, and which are stored behind a heap pointer.
+    pub boxed: Vec<HashSet<Ident>>,
+    pub warnings: Vec<Warning>,
+}
+
+enum Constraint {
+    Equal {
+        expected: Type,
+        found: Type,
+    },
+    Field {
+        of: Type,
+        name: Ident,
+        type_: Type,
+    },
+    /// `of` has the variant `tag` holding `type_`, or, for a pattern naming
+    /// the `mul` `mul`, is that `mul` and the same type as `type_`.
+    Variant {
+        of: Type,
+        tag: Ident,
+        type_: Type,
+        mul: Option<usize>,
+    },
+    /// `of` is a number type with `value` among its values.
+    Number {
+        of: Type,
+        value: u64,
+    },
+}
+
+struct Checker<'a> {
+    program: &'a Program,
+    holes: Vec<Option<Type>>,
+    candidates: Vec<Vec<Type>>,
+    /// Constraints waiting to be solved, each with the function it came from.
+    constraints: Vec<(usize, Constraint)>,
+    deferred: Vec<(usize, Constraint)>,
+    nodes: Vec<Option<Type>>,
+    owners: Vec<usize>,
+    locals: Vec<Vec<Option<Type>>>,
+    schemes: Vec<Option<Box<[usize]>>>,
+    instances: HashMap<usize, Box<[Type]>>,
+    function: usize,
+    /// Every match seen so far, checked for exhaustiveness once all types are solved.
+    matches: Vec<exhaustiveness::Match<'a>>,
+}
+
+impl<'a> Checker<'a> {
+    /// The fields of a struct type, with its type arguments filled in.
+    fn fields(&self, type_: &Type) -> Result<HashMap<Ident, Type>, Error> {
+        match self.resolve(type_) {
+            Type::Item(id, arguments) => match &self.program.types[id].kind {
+                TypeItemKind::Struct(fields) => Ok(instantiate_members(fields, &arguments)),
+                TypeItemKind::Enum(_) => Err(Error::StructExpected(self.zonk(type_))),
+                TypeItemKind::Alias(_) => unreachable!("aliases are expanded where they are named"),
+            },
+            Type::Tuple(_) | Type::Record(_) | Type::Variant(_) | Type::Function(..) | Type::Parameter(_) | Type::Number(_) => Err(Error::StructExpected(self.zonk(type_))),
+            Type::Hole(hole) => Err(self.unsolved(hole)),
+            Type::Alias(..) => unreachable!("resolved types are not aliases"),
+        }
+    }
+
+    /// The variants of an enum type, with its type arguments filled in.
+    fn variants(&self, type_: &Type) -> Result<HashMap<Ident, Type>, Error> {
+        match self.resolve(type_) {
+            Type::Item(id, arguments) => match &self.program.types[id].kind {
+                TypeItemKind::Enum(variants) => Ok(instantiate_members(variants, &arguments)),
+                TypeItemKind::Struct(_) => Err(Error::EnumExpected(self.zonk(type_))),
+                TypeItemKind::Alias(_) => unreachable!("aliases are expanded where they are named"),
+            },
+            Type::Tuple(_) | Type::Record(_) | Type::Variant(_) | Type::Function(..) | Type::Parameter(_) | Type::Number(_) => Err(Error::EnumExpected(self.zonk(type_))),
+            Type::Hole(hole) => Err(self.unsolved(hole)),
+            Type::Alias(..) => unreachable!("resolved types are not aliases"),
+        }
+    }
+
+    /// The type declared by `id`, applied to fresh holes for its parameters.
+    fn item(&mut self, id: usize) -> Type {
+        let arguments = self.program.types[id].parameters.iter().map(|_| self.fresh()).collect();
+        Type::Item(id, arguments)
+    }
+
+    fn field(&self, of: &Type, name: &Ident) -> Result<Type, Error> {
+        let field = match self.resolve(of) {
+            Type::Record(fields) => fields.iter().find(|(field, _)| field == name).map(|(_, type_)| type_.clone()),
+            _ => self.fields(of)?.get(name).cloned(),
+        };
+        field.ok_or_else(|| Error::UnknownField { type_: self.zonk(of), name: name.clone() })
+    }
+
+    fn variant(&self, of: &Type, tag: &Ident) -> Result<Type, Error> {
+        let variant = match self.resolve(of) {
+            Type::Variant(variants) => variants.iter().find(|(variant, _)| variant == tag).map(|(_, type_)| type_.clone()),
+            _ => self.variants(of)?.get(tag).cloned(),
+        };
+        variant.ok_or_else(|| Error::UnknownTag { type_: self.zonk(of), tag: tag.clone() })
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.holes.push(None);
+        self.candidates.push(Vec::new());
+        Type::Hole(self.holes.len() - 1)
+    }
+
+    /// Follows solved holes, keeping the alias a type was written with.
+    fn resolve_holes(&self, type_: &Type) -> Type {
+        match type_ {
+            Type::Hole(hole) => match &self.holes[*hole] {
+                Some(solution) => self.resolve_holes(solution),
+                None => type_.clone(),
+            },
+            _ => type_.clone(),
+        }
+    }
+
+    /// Follows solved holes and aliases down to the type's own shape.
+    fn resolve(&self, type_: &Type) -> Type {
+        match self.resolve_holes(type_) {
+            Type::Alias(_, _, expansion) => self.resolve(&expansion),
+            type_ => type_,
+        }
+    }
+
+    fn zonk(&self, type_: &Type) -> Type {
+        match self.resolve_holes(type_) {
+            Type::Alias(id, arguments, expansion) => Type::Alias(
+                id,
+                arguments.iter().map(|argument| self.zonk(argument)).collect(),
+                Box::new(self.zonk(&expansion)),
+            ),
+            Type::Item(id, arguments) => Type::Item(id, arguments.iter().map(|argument| self.zonk(argument)).collect()),
+            Type::Tuple(items) => Type::Tuple(items.iter().map(|item| self.zonk(item)).collect()),
+            Type::Record(fields) => Type::Record(self.zonk_members(&fields)),
+            Type::Variant(variants) => Type::Variant(self.zonk_members(&variants)),
+            Type::Function(input, output) => Type::Function(Box::new(self.zonk(&input)), Box::new(self.zonk(&output))),
+            type_ => type_,
+        }
+    }
+
+    fn zonk_members(&self, members: &[(Ident, Type)]) -> Box<[(Ident, Type)]> {
+        members.iter().map(|(name, type_)| (name.clone(), self.zonk(type_))).collect()
+    }
+
+    fn unsolved(&self, hole: usize) -> Error {
+        Error::Hole {
+            candidates: self.candidates[hole].iter().map(|type_| self.zonk(type_)).collect(),
+        }
+    }
+
+    /// Zonks `type_`, failing on any hole that is not one of the `generic` ones.
+    fn solved(&self, type_: &Type, generic: &[usize]) -> Result<Type, Error> {
+        match self.resolve(type_) {
+            Type::Item(id, arguments) => Ok(Type::Item(id,
+                arguments.iter()
+                    .map(|argument| self.solved(argument, generic))
+                    .collect::<Result<_, Error>>()?
+            )),
+            Type::Tuple(items) => Ok(Type::Tuple(
+                items.iter()
+                    .map(|item| self.solved(item, generic))
+                    .collect::<Result<_, Error>>()?
+            )),
+            Type::Record(fields) => Ok(Type::Record(self.solved_members(&fields, generic)?)),
+            Type::Variant(variants) => Ok(Type::Variant(self.solved_members(&variants, generic)?)),
+            Type::Function(input, output) => Ok(Type::Function(
+                Box::new(self.solved(&input, generic)?),
+                Box::new(self.solved(&output, generic)?),
+            )),
+            Type::Hole(hole) if !generic.contains(&hole) => Err(self.unsolved(hole)),
+            Type::Alias(..) => unreachable!("resolved types are not aliases"),
+            type_ => Ok(type_),
+        }
+    }
+
+    fn solved_members(&self, members: &[(Ident, Type)], generic: &[usize]) -> Result<Box<[(Ident, Type)]>, Error> {
+        members.iter()
+            .map(|(name, type_)| Ok((name.clone(), self.solved(type_, generic)?)))
+            .collect()
+    }
+
+    fn free(&self, type_: &Type, holes: &mut HashSet<usize>) {
+        match self.resolve(type_) {
+            Type::Hole(hole) => {
+                holes.insert(hole);
+            },
+            Type::Item(_, items) | Type::Tuple(items) => items.iter().for_each(|item| self.free(item, holes)),
+            Type::Record(members) | Type::Variant(members) => members.iter().for_each(|(_, type_)| self.free(type_, holes)),
+            Type::Function(input, output) => {
+                self.free(&input, holes);
+                self.free(&output, holes);
+            },
+            Type::Parameter(_) | Type::Number(_) => {},
+            Type::Alias(..) => unreachable!("resolved types are not aliases"),
+        }
+    }
+
+    fn occurs(&self, hole: usize, type_: &Type) -> bool {
+        match self.resolve(type_) {
+            Type::Hole(other) => hole == other,
+            Type::Item(_, items) | Type::Tuple(items) => items.iter().any(|item| self.occurs(hole, item)),
+            Type::Record(members) | Type::Variant(members) => members.iter().any(|(_, type_)| self.occurs(hole, type_)),
+            Type::Function(input, output) => self.occurs(hole, &input) || self.occurs(hole, &output),
+            Type::Parameter(_) | Type::Number(_) => false,
+            Type::Alias(..) => unreachable!("resolved types are not aliases"),
+        }
+    }
+
+    fn bind(&mut self, hole: usize, type_: &Type) -> Result<(), Error> {
+        if let Type::Hole(other) = self.resolve(type_) {
+            if other == hole {
+                return Ok(());
+            }
+        }
+
+        self.candidates[hole].push(type_.clone());
+
+        match self.holes[hole].clone() {
+            Some(solution) => self.unify(&solution, type_),
+            None => if self.occurs(hole, type_) {
+                Err(self.unsolved(hole))
+            } else {
+                self.holes[hole] = Some(type_.clone());
+                Ok(())
+            },
+        }
+    }
+
+    fn unify(&mut self, expected: &Type, found: &Type) -> Result<(), Error> {
+        // Solved holes are followed first, so a mismatch with their solution
+        // keeps which side was expected.
+        for (hole, other, is_expected) in [(expected, found, true), (found, expected, false)] {
+            if let Type::Hole(hole) = hole {
+                if let Some(solution) = self.holes[*hole].clone() {
+                    return match is_expected {
+                        true => self.unify(&solution, other),
+                        false => self.unify(other, &solution),
+                    };
+                }
+            }
+        }
+
+        match (expected, found) {
+            (Type::Hole(hole), other) | (other, Type::Hole(hole)) => self.bind(*hole, other),
+            (Type::Alias(_, _, expansion), _) => self.unify(expansion, found).map_err(|e| self.named(e, expected, found)),
+            (_, Type::Alias(_, _, expansion)) => self.unify(expected, expansion).map_err(|e| self.named(e, expected, found)),
+            (Type::Item(left_id, left), Type::Item(right_id, right)) if left_id == right_id => {
+                for (left, right) in left.iter().zip(right.iter()) {
+                    self.unify(left, right)?;
+                }
+                Ok(())
+            },
+            (Type::Tuple(left), Type::Tuple(right)) if left.len() == right.len() => {
+                for (left, right) in left.iter().zip(right.iter()) {
+                    self.unify(left, right)?;
+                }
+                Ok(())
+            },
+            (Type::Record(left), Type::Record(right)) | (Type::Variant(left), Type::Variant(right))
+                if left.iter().map(|(name, _)| name).eq(right.iter().map(|(name, _)| name)) =>
+            {
+                for ((_, left), (_, right)) in left.iter().zip(right.iter()) {
+                    self.unify(left, right)?;
+                }
+                Ok(())
+            },
+            (Type::Function(left_input, left_output), Type::Function(right_input, right_output)) => {
+                self.unify(left_input, right_input)?;
+                self.unify(left_output, right_output)
+            },
+            (Type::Number(left), Type::Number(right)) if left == right => Ok(()),
+            _ => Err(Error::Mismatch { expected: self.zonk(expected), found: self.zonk(found) }),
+        }
+    }
+
+    /// Reports a mismatch found inside an alias's expansion as one between
+    /// the types as written, so the alias keeps its name.
+    fn named(&self, e: Error, expected: &Type, found: &Type) -> Error {
+        match e {
+            Error::Mismatch { .. } => Error::Mismatch { expected: self.zonk(expected), found: self.zonk(found) },
+            e => e,
+        }
+    }
+
+    fn equal(&mut self, expected: Type, found: Type) {
+        self.constrain(Constraint::Equal { expected, found });
+    }
+
+    fn constrain(&mut self, constraint: Constraint) {
+        self.constraints.push((self.function, constraint));
+    }
+
+    fn node(&mut self, id: usize, type_: Type) -> Type {
+        self.nodes[id] = Some(type_.clone());
+        self.owners[id] = self.function;
+        type_
+    }
+
+    fn local(&self, id: usize) -> Type {
+        self.locals[self.function][id].clone().expect("locals are typed before they are referenced")
+    }
+
+    fn bind_local(&mut self, id: usize, type_: Type) {
+        self.locals[self.function][id] = Some(type_);
+    }
+
+    /// The signature of `function` as seen from the call `id`, instantiated
+    /// with fresh holes once the function has been generalized.
+    fn signature(&mut self, id: usize, function: usize) -> (Type, Type) {
+        let function_ = &self.program.functions[function];
+        let (input, output) = (self.zonk(&function_.input), self.zonk(&function_.output));
+
+        match self.schemes[function].clone() {
+            Some(generic) if !generic.is_empty() => {
+                let instance: Box<[Type]> = generic.iter().map(|_| self.fresh()).collect();
+                let substitution: HashMap<usize, Type> = generic.iter().copied().zip(instance.iter().cloned()).collect();
+                self.instances.insert(id, instance);
+                (input.substitute(&substitution), output.substitute(&substitution))
+            },
+            _ => (input, output),
+        }
+    }
+
+    fn infer(&mut self, value: &'a Expression) -> Result<Type, Error> {
+        match value {
+            Expression::Mul { id, type_, base, fields } => {
+                let type_ = self.item(*type_);
+                let expected = self.fields(&type_)?;
+
+                match base {
+                    Some(base) => self.expect(base, type_.clone())?,
+                    None => for name in expected.keys() {
+                        if !fields.contains_key(name) {
+                            Err(Error::MissingField { type_: type_.clone(), name: name.clone() })?
+                        }
+                    },
+                }
+
+                for (name, value) in fields {
+                    let field = expected.get(name)
+                        .ok_or_else(|| Error::UnknownField { type_: type_.clone(), name: name.clone() })?
+                        .clone();
+                    self.expect(value, field)?;
+                }
+
+                Ok(self.node(*id, type_))
+            },
+
+            Expression::Sum { id, type_, tag, body } => {
+                let type_ = self.item(*type_);
+                let variant = self.variants(&type_)?.get(tag)
+                    .ok_or_else(|| Error::UnknownTag { type_: type_.clone(), tag: tag.clone() })?
+                    .clone();
+                self.expect(body, variant)?;
+
+                Ok(self.node(*id, type_))
+            },
+
+            Expression::Call { id, function, input } => {
+                let (input_type, output) = self.signature(*id, *function);
+                self.expect(input, input_type)?;
+
+                Ok(self.node(*id, output))
+            },
+
+            Expression::Match { id, on, variants, source } => {
+                if variants.is_empty() {
+                    Err(Error::EmptyMatch)?
+                }
+
+                let on = self.infer(on)?;
+                self.matches.push(exhaustiveness::Match {
+                    function: self.function,
+                    on: on.clone(),
+                    variants,
+                    source: *source,
+                });
+                let result = self.fresh();
+
+                for (pattern, value) in variants.iter() {
+                    self.pattern(pattern, &on)?;
+                    self.expect(value, result.clone())?;
+                }
+
+                Ok(self.node(*id, result))
+            },
+
+            Expression::Member { id, of, name } => {
+                let of = self.infer(of)?;
+                let type_ = self.fresh();
+                self.constrain(Constraint::Field { of, name: name.clone(), type_: type_.clone() });
+
+                Ok(self.node(*id, type_))
+            },
+
+            Expression::Variable { id, local } => {
+                let type_ = self.local(*local);
+                Ok(self.node(*id, type_))
+            },
+
+            Expression::Block { statements, result } => {
+                for statement in statements.iter() {
+                    match statement {
+                        Statement::Let { local, value } => {
+                            let type_ = self.infer(value)?;
+                            self.bind_local(*local, type_);
+                        },
+                        Statement::Expression(value) => {
+                            self.infer(value)?;
+                        },
+                    }
+                }
+
+                self.infer(result)
+            },
+
+            Expression::Debug { expression, .. } => self.infer(expression),
+
+            Expression::Tuple(items) => Ok(Type::Tuple(
+                items.iter()
+                    .map(|item| self.infer(item))
+                    .collect::<Result<_, Error>>()?
+            )),
+
+            Expression::Record(fields) => Ok(Type::Record(canonical(
+                fields.iter()
+                    .map(|(name, value)| Ok((name.clone(), self.infer(value)?)))
+                    .collect::<Result<_, Error>>()?
+            ))),
+
+            Expression::Variant { id, tag, body } => {
+                let type_ = self.fresh();
+                let body = self.infer(body)?;
+                self.constrain(Constraint::Variant { of: type_.clone(), tag: tag.clone(), type_: body, mul: None });
+
+                Ok(self.node(*id, type_))
+            },
+
+            Expression::Lambda { id, input, body, .. } => {
+                let input_type = self.fresh();
+                self.bind_local(*input, input_type.clone());
+                let output = self.infer(body)?;
+
+                Ok(self.node(*id, Type::Function(Box::new(input_type), Box::new(output))))
+            },
+
+            Expression::Apply { id, function, input } => {
+                let output = self.fresh();
+                let input = self.infer(input)?;
+                self.expect(function, Type::Function(Box::new(input), Box::new(output.clone())))?;
+
+                Ok(self.node(*id, output))
+            },
+
+            Expression::Function { id, function } => {
+                let (input, output) = self.signature(*id, *function);
+                Ok(self.node(*id, Type::Function(Box::new(input), Box::new(output))))
+            },
+
+            Expression::Number { id, value } => {
+                let type_ = self.fresh();
+                self.constrain(Constraint::Number { of: type_.clone(), value: *value });
+                Ok(self.node(*id, type_))
+            },
+        }
+    }
+
+    fn pattern(&mut self, value: &'a Pattern, expected: &Type) -> Result<(), Error> {
+        match value {
+            Pattern::Equals(value) => self.expect(value, expected.clone()),
+
+            Pattern::Tag { tag, body, mul } => {
+                let type_ = self.fresh();
+                self.constrain(Constraint::Variant { of: expected.clone(), tag: tag.clone(), type_: type_.clone(), mul: *mul });
+                self.pattern(body, &type_)
+            },
+
+            Pattern::Fields(fields) => {
+                for (name, value) in fields {
+                    let type_ = self.fresh();
+                    self.constrain(Constraint::Field { of: expected.clone(), name: name.clone(), type_: type_.clone() });
+                    self.pattern(value, &type_)?;
+                }
+                Ok(())
+            },
+
+            Pattern::Any(local) => {
+                self.bind_pattern_local(*local, expected);
+                Ok(())
+            },
+
+            Pattern::Wildcard => Ok(()),
+
+            Pattern::Tuple(items) => {
+                let types: Box<[Type]> = items.iter().map(|_| self.fresh()).collect();
+                self.equal(expected.clone(), Type::Tuple(types.clone()));
+                for (item, type_) in items.iter().zip(types.iter()) {
+                    self.pattern(item, type_)?;
+                }
+                Ok(())
+            },
+
+            Pattern::Or(alternatives) => {
+                for alternative in alternatives.iter() {
+                    self.pattern(alternative, expected)?;
+                }
+                Ok(())
+            },
+
+            Pattern::Bind(local, pattern) => {
+                self.bind_pattern_local(*local, expected);
+                self.pattern(pattern, expected)
+            },
+
+            Pattern::Guard { pattern, guard, on } => {
+                self.pattern(pattern, expected)?;
+                let on = self.infer(on)?;
+                self.pattern(guard, &on)
+            },
+        }
+    }
+
+    /// Binds a local of a pattern, which every alternative of an or-pattern
+    /// binds again at the same type.
+    fn bind_pattern_local(&mut self, id: usize, type_: &Type) {
+        match self.locals[self.function][id].clone() {
+            Some(bound) => self.equal(bound, type_.clone()),
+            None => self.bind_local(id, type_.clone()),
+        }
+    }
+
+    fn expect(&mut self, value: &'a Expression, expected: Type) -> Result<(), Error> {
+        let found = self.infer(value)?;
+        self.equal(expected, found);
+        Ok(())
+    }
+
+    fn function(&mut self, id: usize) -> Result<(), Error> {
+        let value = &self.program.functions[id];
+        self.function = id;
+        self.locals[id] = vec![None; value.locals];
+        self.locals[id][0] = Some(value.input.clone());
+
+        self.expect(&value.expression, value.output.clone())
+    }
+
+    /// Tries a field or variant constraint, handing it back while its
+    /// subject is still an unsolved hole.
+    fn constraint(&mut self, value: Constraint) -> Result<Option<Constraint>, Error> {
+        match value {
+            Constraint::Equal { expected, found } => self.unify(&expected, &found).map(|_| None),
+
+            Constraint::Field { of, name, type_ } => match self.resolve(&of) {
+                Type::Hole(_) => Ok(Some(Constraint::Field { of, name, type_ })),
+                _ => {
+                    let field = self.field(&of, &name)?;
+                    self.unify(&field, &type_).map(|_| None)
+                },
+            },
+
+            Constraint::Variant { of, tag, type_, mul } => match self.resolve(&of) {
+                Type::Hole(_) => Ok(Some(Constraint::Variant { of, tag, type_, mul })),
+                Type::Item(id, _) if mul == Some(id) => self.unify(&of, &type_).map(|_| None),
+                _ => {
+                    let variant = self.variant(&of, &tag)?;
+                    self.unify(&variant, &type_).map(|_| None)
+                },
+            },
+
+            Constraint::Number { of, value } => match self.resolve(&of) {
+                Type::Hole(_) => Ok(Some(Constraint::Number { of, value })),
+                Type::Number(number) if value < number => Ok(None),
+                _ => Err(Error::Number { value, type_: self.zonk(&of) }),
+            },
+        }
+    }
+
+    fn solve(&mut self) -> Result<(), Error> {
+        let mut pending: Vec<(usize, Constraint)> = self.constraints.drain(..).collect();
+        pending.append(&mut self.deferred);
+
+        loop {
+            let before = pending.len();
+            for (function, constraint) in std::mem::take(&mut pending) {
+                let constraint = self.constraint(constraint).map_err(|e| e.in_function(function))?;
+                if let Some(constraint) = constraint {
+                    pending.push((function, constraint));
+                }
+            }
+
+            if pending.len() == before {
+                break;
+            }
+        }
+
+        self.deferred = pending;
+        Ok(())
+    }
+
+    /// Settles one hole that only variant constraints are still waiting on,
+    /// taking the `mul` a pattern names, or else the anonymous variant of
+    /// every tag used with it.
+    fn default_variant(&mut self) -> Result<bool, Error> {
+        let hole = self.deferred.iter().find_map(|(function, constraint)| match constraint {
+            Constraint::Variant { of, .. } => match self.resolve(of) {
+                Type::Hole(hole) => Some((hole, *function)),
+                _ => None,
+            },
+            _ => None,
+        });
+        let Some((hole, function)) = hole else {
+            return Ok(false)
+        };
+
+        let mut variants: Vec<(Ident, Type)> = Vec::new();
+        let mut mul = None;
+        for (_, constraint) in &self.deferred {
+            match constraint {
+                Constraint::Variant { of, tag, type_, mul: named } if self.resolve(of) == Type::Hole(hole) => {
+                    mul = mul.or(*named);
+                    if !variants.iter().any(|(other, _)| other == tag) {
+                        variants.push((tag.clone(), type_.clone()));
+                    }
+                },
+                Constraint::Field { of, .. } if self.resolve(of) == Type::Hole(hole) => return Ok(false),
+                _ => {},
+            }
+        }
+
+        let type_ = match mul {
+            Some(id) => self.item(id),
+            None => Type::Variant(canonical(variants)),
+        };
+        self.bind(hole, &type_).map_err(|e| e.in_function(function))?;
+        self.solve()?;
+        Ok(true)
+    }
+
+    /// Settles one hole that only number literals are still waiting on, as
+    /// the smallest number type holding all of them.
+    fn default_number(&mut self) -> Result<bool, Error> {
+        let hole = self.deferred.iter().find_map(|(function, constraint)| match constraint {
+            Constraint::Number { of, .. } => match self.resolve(of) {
+                Type::Hole(hole) => Some((hole, *function)),
+                _ => None,
+            },
+            _ => None,
+        });
+        let Some((hole, function)) = hole else {
+            return Ok(false)
+        };
+
+        let mut max = 0;
+        for (_, constraint) in &self.deferred {
+            match constraint {
+                Constraint::Number { of, value } if self.resolve(of) == Type::Hole(hole) => max = max.max(*value),
+                Constraint::Field { of, .. } | Constraint::Variant { of, .. } if self.resolve(of) == Type::Hole(hole) => return Ok(false),
+                _ => {},
+            }
+        }
+
+        let type_ = Type::Number(max.saturating_add(1));
+        self.bind(hole, &type_).map_err(|e| e.in_function(function))?;
+        self.solve()?;
+        Ok(true)
+    }
+
+    /// Holes that may not be generalized: those shared with type
+    /// declarations and those still waiting on a field or variant.
+    fn monomorphic(&self) -> HashSet<usize> {
+        let mut holes = HashSet::new();
+
+        for item in &self.program.types {
+            match &item.kind {
+                TypeItemKind::Struct(types) | TypeItemKind::Enum(types) => {
+                    types.values().for_each(|type_| self.free(type_, &mut holes));
+                },
+                TypeItemKind::Alias(type_) => self.free(type_, &mut holes),
+            }
+        }
+
+        for (_, constraint) in &self.deferred {
+            match constraint {
+                Constraint::Equal { expected, found } => {
+                    self.free(expected, &mut holes);
+                    self.free(found, &mut holes);
+                },
+                Constraint::Field { of, type_, .. } | Constraint::Variant { of, type_, .. } => {
+                    self.free(of, &mut holes);
+                    self.free(type_, &mut holes);
+                },
+                Constraint::Number { of, .. } => self.free(of, &mut holes),
+            }
+        }
+
+        holes
+    }
+
+    fn component(&mut self, functions: &[usize]) -> Result<(), Error> {
+        for &id in functions {
+            self.function(id).map_err(|e| e.in_function(id))?;
+        }
+        self.solve()?;
+
+        // Signatures are held against what the definitions make of their
+        // functions, so a disagreement names the signature.
+        for &id in functions {
+            let function = &self.program.functions[id];
+            if let Some((input, output)) = &function.signature {
+                let signature = Type::Function(Box::new(input.clone()), Box::new(output.clone()));
+                let found = Type::Function(Box::new(function.input.clone()), Box::new(function.output.clone()));
+                let zonked = self.zonk(&found);
+                if self.unify(&signature, &found).is_err() {
+                    return Err(Error::Signature { function: id, signature: self.zonk(&signature), found: zonked });
+                }
+            }
+        }
+        self.solve()?;
+
+        let monomorphic = self.monomorphic();
+        for &id in functions {
+            let function = &self.program.functions[id];
+            let mut holes = HashSet::new();
+            self.free(&function.input, &mut holes);
+            self.free(&function.output, &mut holes);
+
+            let mut generic: Vec<usize> = holes.difference(&monomorphic).copied().collect();
+            generic.sort();
+            self.schemes[id] = Some(generic.into());
+        }
+
+        Ok(())
+    }
+}
+
+fn calls(value: &Expression, callees: &mut Vec<usize>) {
+    match value {
+        Expression::Mul { base, fields, .. } => {
+            if let Some(base) = base {
+                calls(base, callees);
+            }
+            fields.values().for_each(|value| calls(value, callees));
+        },
+        Expression::Sum { body, .. } => calls(body, callees),
+        Expression::Call { function, input, .. } => {
+            callees.push(*function);
+            calls(input, callees);
+        },
+        Expression::Match { on, variants, .. } => {
+            calls(on, callees);
+            for (pattern, value) in variants.iter() {
+                pattern_calls(pattern, callees);
+                calls(value, callees);
+            }
+        },
+        Expression::Member { of, .. } => calls(of, callees),
+        Expression::Variable { .. } => {},
+        Expression::Block { statements, result } => {
+            for statement in statements.iter() {
+                match statement {
+                    Statement::Let { value, .. } | Statement::Expression(value) => calls(value, callees),
+                }
+            }
+            calls(result, callees);
+        },
+        Expression::Debug { expression, .. } => calls(expression, callees),
+        Expression::Tuple(items) => items.iter().for_each(|item| calls(item, callees)),
+        Expression::Record(fields) => fields.values().for_each(|value| calls(value, callees)),
+        Expression::Variant { body, .. } | Expression::Lambda { body, .. } => calls(body, callees),
+        Expression::Apply { function, input, .. } => {
+            calls(function, callees);
+            calls(input, callees);
+        },
+        Expression::Function { function, .. } => callees.push(*function),
+        Expression::Number { .. } => {},
+    }
+}
+
+fn pattern_calls(value: &Pattern, callees: &mut Vec<usize>) {
+    match value {
+        Pattern::Equals(value) => calls(value, callees),
+        Pattern::Tag { body, .. } => pattern_calls(body, callees),
+        Pattern::Fields(fields) => fields.values().for_each(|value| pattern_calls(value, callees)),
+        Pattern::Any(_) | Pattern::Wildcard => {},
+        Pattern::Tuple(items) | Pattern::Or(items) => items.iter().for_each(|item| pattern_calls(item, callees)),
+        Pattern::Bind(_, pattern) => pattern_calls(pattern, callees),
+        Pattern::Guard { pattern, guard, on } => {
+            pattern_calls(pattern, callees);
+            pattern_calls(guard, callees);
+            calls(on, callees);
+        },
+    }
+}
+
+/// Every declaration `type_` mentions, directly or through type arguments,
+/// with the arguments it is applied to. Function types mention nothing, as a
+/// closure is the same size whatever it takes and returns.
+fn mentions<'a>(type_: &'a Type, found: &mut Vec<(usize, &'a [Type])>) {
+    match type_ {
+        Type::Item(id, arguments) => {
+            found.push((*id, arguments));
+            arguments.iter().for_each(|argument| mentions(argument, found));
+        },
+        Type::Tuple(items) => items.iter().for_each(|item| mentions(item, found)),
+        Type::Record(members) | Type::Variant(members) => members.iter().for_each(|(_, type_)| mentions(type_, found)),
+        Type::Alias(_, _, expansion) => mentions(expansion, found),
+        Type::Hole(_) | Type::Parameter(_) | Type::Function(..) | Type::Number(_) => {},
+    }
+}
+
+/// The members of each declaration that would make its layout infinite if
+/// stored inline: those mentioning a declaration of its own component. Such
+/// a mention must apply the declaration to bare type parameters, as in
+/// `List<T>` inside `List<T>`, so every type only needs finitely many.
+fn boxed(program: &Program) -> Result<Vec<HashSet<Ident>>, Error> {
+    let empty = HashMap::new();
+    let members = |id: usize| match &program.types[id].kind {
+        TypeItemKind::Struct(members) | TypeItemKind::Enum(members) => members,
+        TypeItemKind::Alias(_) => &empty,
+    };
+    let edges: Vec<Vec<usize>> = (0..program.types.len())
+        .map(|id| {
+            let mut items = Vec::new();
+            members(id).values().for_each(|type_| mentions(type_, &mut items));
+            items.into_iter().map(|(item, _)| item).collect()
+        })
+        .collect();
+
+    let mut boxed = vec![HashSet::new(); program.types.len()];
+    for component in Components::new(edges.clone()).components {
+        if component.len() == 1 && !edges[component[0]].contains(&component[0]) {
+            continue;
+        }
+
+        for &id in &component {
+            for (name, type_) in members(id) {
+                let mut items = Vec::new();
+                mentions(type_, &mut items);
+                for (item, arguments) in items {
+                    if !component.contains(&item) {
+                        continue;
+                    }
+                    if !arguments.iter().all(|argument| matches!(argument, Type::Parameter(_))) {
+                        return Err(Error::NonRegular { type_: id, member: name.clone(), mentioned: item });
+                    }
+                    boxed[id].insert(name.clone());
+                }
+            }
+        }
+    }
+    Ok(boxed)
+}
+
+/// Strongly connected components of a graph given by its edges, with the
+/// components a node reaches listed before it. Used for both the call graph
+/// and the graph of type declarations.
+struct Components {
+    edges: Vec<Vec<usize>>,
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    next: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Components {
+    fn new(edges: Vec<Vec<usize>>) -> Self {
+        let len = edges.len();
+        let mut components = Self {
+            edges,
+            index: vec![None; len],
+            low: vec![0; len],
+            stack: Vec::new(),
+            on_stack: vec![false; len],
+            next: 0,
+            components: Vec::new(),
+        };
+
+        for id in 0..len {
+            if components.index[id].is_none() {
+                components.visit(id);
+            }
+        }
+
+        components
+    }
+
+    fn visit(&mut self, id: usize) {
+        self.index[id] = Some(self.next);
+        self.low[id] = self.next;
+        self.next += 1;
+        self.stack.push(id);
+        self.on_stack[id] = true;
+
+        for callee in self.edges[id].clone() {
+            match self.index[callee] {
+                None => {
+                    self.visit(callee);
+                    self.low[id] = self.low[id].min(self.low[callee]);
+                },
+                Some(index) if self.on_stack[callee] => self.low[id] = self.low[id].min(index),
+                Some(_) => {},
+            }
+        }
+
+        if Some(self.low[id]) == self.index[id] {
+            let mut component = Vec::new();
+            while let Some(other) = self.stack.pop() {
+                self.on_stack[other] = false;
+                component.push(other);
+                if other == id {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+impl Type {
+    pub fn substitute(&self, substitution: &HashMap<usize, Type>) -> Type {
+        match self {
+            Self::Hole(hole) => substitution.get(hole).cloned().unwrap_or_else(|| self.clone()),
+            Self::Item(id, arguments) => Self::Item(*id, arguments.iter().map(|argument| argument.substitute(substitution)).collect()),
+            Self::Tuple(items) => Self::Tuple(items.iter().map(|item| item.substitute(substitution)).collect()),
+            Self::Record(fields) => Self::Record(substitute_members(fields, substitution)),
+            Self::Variant(variants) => Self::Variant(substitute_members(variants, substitution)),
+            Self::Function(input, output) => Self::Function(Box::new(input.substitute(substitution)), Box::new(output.substitute(substitution))),
+            Self::Parameter(_) | Self::Number(_) => self.clone(),
+            Self::Alias(id, arguments, expansion) => Self::Alias(
+                *id,
+                arguments.iter().map(|argument| argument.substitute(substitution)).collect(),
+                Box::new(expansion.substitute(substitution)),
+            ),
+        }
+    }
+
+    /// Replaces the parameters of a declaration's member type with `arguments`.
+    pub fn instantiate(&self, arguments: &[Type]) -> Type {
+        match self {
+            Self::Parameter(index) => arguments[*index].clone(),
+            Self::Item(id, items) => Self::Item(*id, items.iter().map(|item| item.instantiate(arguments)).collect()),
+            Self::Tuple(items) => Self::Tuple(items.iter().map(|item| item.instantiate(arguments)).collect()),
+            Self::Record(fields) => Self::Record(fields.iter().map(|(name, type_)| (name.clone(), type_.instantiate(arguments))).collect()),
+            Self::Variant(variants) => Self::Variant(variants.iter().map(|(name, type_)| (name.clone(), type_.instantiate(arguments))).collect()),
+            Self::Function(input, output) => Self::Function(Box::new(input.instantiate(arguments)), Box::new(output.instantiate(arguments))),
+            Self::Hole(_) | Self::Number(_) => self.clone(),
+            Self::Alias(id, items, expansion) => Self::Alias(
+                *id,
+                items.iter().map(|item| item.instantiate(arguments)).collect(),
+                Box::new(expansion.instantiate(arguments)),
+            ),
+        }
+    }
+}
+
+fn instantiate_members(members: &HashMap<Ident, Type>, arguments: &[Type]) -> HashMap<Ident, Type> {
+    members.iter().map(|(name, type_)| (name.clone(), type_.instantiate(arguments))).collect()
+}
+
+fn substitute_members(members: &[(Ident, Type)], substitution: &HashMap<usize, Type>) -> Box<[(Ident, Type)]> {
+    members.iter().map(|(name, type_)| (name.clone(), type_.substitute(substitution))).collect()
+}
+
+/// Infers a type for every node of `program`, generalizing each function
+/// over the holes of its signature that nothing else pins down.
+pub fn check(program: &mut Program) -> Result<Table, Error> {
+    let mut checker = Checker {
+        program,
+        holes: vec![None; program.holes],
+        candidates: vec![Vec::new(); program.holes],
+        constraints: Vec::new(),
+        deferred: Vec::new(),
+        nodes: vec![None; program.nodes],
+        owners: vec![0; program.nodes],
+        locals: vec![Vec::new(); program.functions.len()],
+        schemes: vec![None; program.functions.len()],
+        instances: HashMap::new(),
+        function: 0,
+        matches: Vec::new(),
+    };
+
+    let calls = program.functions.iter()
+        .map(|function| {
+            let mut callees = Vec::new();
+            calls(&function.expression, &mut callees);
+            callees
+        })
+        .collect();
+
+    for component in Components::new(calls).components {
+        checker.component(&component)?;
+    }
+
+    while checker.default_variant()? || checker.default_number()? {}
+
+    if let Some((function, constraint)) = checker.deferred.first() {
+        match constraint {
+            Constraint::Field { of, .. } | Constraint::Variant { of, .. } | Constraint::Number { of, .. } => checker.solved(of, &[]),
+            Constraint::Equal { expected, .. } => checker.solved(expected, &[]),
+        }.map_err(|e| e.in_function(*function))?;
+    }
+
+    let schemes: Vec<Box<[usize]>> = checker.schemes.iter()
+        .map(|scheme| scheme.clone().expect("every function belongs to a component"))
+        .collect();
+
+    let nodes = checker.nodes.iter().zip(checker.owners.iter())
+        .map(|(type_, &owner)| checker.solved(
+            type_.as_ref().expect("every node is typed by its function"),
+            &schemes[owner],
+        ).map_err(|e| e.in_function(owner)))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let locals = checker.locals.iter().zip(schemes.iter()).enumerate()
+        .map(|(function, (locals, generic))| locals.iter()
+            .map(|local| checker.solved(
+                local.as_ref().expect("every local is bound by a checked let or pattern"),
+                generic,
+            ).map_err(|e| e.in_function(function)))
+            .collect())
+        .collect::<Result<_, Error>>()?;
+
+    let instances = checker.instances.iter()
+        .map(|(&id, instance)| Ok((id, instance.iter()
+            .map(|type_| checker.solved(type_, &schemes[checker.owners[id]]).map_err(|e| e.in_function(checker.owners[id])))
+            .collect::<Result<_, Error>>()?)))
+        .collect::<Result<_, Error>>()?;
+
+    let mut warnings = Vec::new();
+    for value in &checker.matches {
+        checker.exhaustive(value, &mut warnings)?;
+    }
+
+    let signatures = program.functions.iter().zip(schemes.iter()).enumerate()
+        .map(|(id, (function, generic))| Ok((
+            checker.solved(&function.input, generic).map_err(|e| e.in_function(id))?,
+            checker.solved(&function.output, generic).map_err(|e| e.in_function(id))?,
+        )))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let declarations = program.types.iter()
+        .map(|item| match &item.kind {
+            TypeItemKind::Struct(types) | TypeItemKind::Enum(types) => types.iter()
+                .map(|(name, type_)| Ok((name.clone(), checker.solved(type_, &[])?)))
+                .collect::<Result<HashMap<_, _>, Error>>()
+                .map(Some),
+            TypeItemKind::Alias(_) => Ok(None),
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    for (function, (input, output)) in program.functions.iter_mut().zip(signatures) {
+        function.input = input;
+        function.output = output;
+    }
+
+    for (item, types) in program.types.iter_mut().zip(declarations) {
+        if let (TypeItemKind::Struct(fields) | TypeItemKind::Enum(fields), Some(types)) = (&mut item.kind, types) {
+            *fields = types;
+        }
+    }
+
+    let boxed = boxed(program)?;
+    Ok(Table { nodes, locals, schemes, instances, boxed, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{checker, loader, referencer};
+
+    /// Checks `source`, giving back its first error as the driver prints it.
+    fn error(source: &str) -> String {
+        let (root, _) = loader::source("test.sn", source).expect("the source parses");
+        let mut program = referencer::resolve(&root).expect("the source resolves");
+        match checker::check(&mut program) {
+            Ok(_) => panic!("expected a type error"),
+            Err(error) => program.named(&error).to_string(),
+        }
+    }
+
+    const COLOR: &str = "sum Color { Red, Green }";
+
+    #[test]
+    fn mismatch() {
+        let source = format!("{COLOR} fn f () -> Color x {{ () }}");
+        assert_eq!(error(&source), "in `f`: expected `Color`, found `()`");
+    }
+
+    #[test]
+    fn mismatch_with_solved_hole() {
+        let source = format!("{COLOR} fn f ? -> () x {{ let a = f (); f Color:Red () }}");
+        assert_eq!(error(&source), "in `f`: expected `()`, found `Color`");
+    }
+
+    #[test]
+    fn number_out_of_range() {
+        assert_eq!(error("fn f () -> 3 x { 5 }"), "in `f`: `5` is not a value of `3`");
+    }
+
+    #[test]
+    fn alias_names() {
+        assert_eq!(error("type Score = 5 ^ 2; fn f () -> Score x { 25 }"), "in `f`: `25` is not a value of `Score`");
+
+        let source = format!("{COLOR} type Pair = (Color, Color); fn f () -> Pair x {{ () }}");
+        assert_eq!(error(&source), "in `f`: expected `Pair`, found `()`");
+    }
+
+    #[test]
+    fn infinite_hole() {
+        let source = "fn f ? -> () x { let y = (x, x); f y }";
+        assert_eq!(error(source), "in `f`: cannot infer a type, it could be `(?, ?)`");
+    }
+
+    #[test]
+    fn signature_mismatch() {
+        let source = format!("{COLOR} paint :: Color -> () fn paint Color -> Color c {{ c }}");
+        assert_eq!(error(&source), "the signature `paint :: Color -> ()` does not match `fn paint`, which is `Color -> Color`");
+    }
+
+    #[test]
+    fn signature_against_holes() {
+        let source = format!("{COLOR} paint :: Color -> () fn paint ? -> ? c {{ c }}");
+        assert_eq!(error(&source), "the signature `paint :: Color -> ()` does not match `fn paint`, which is `? -> ?`");
+    }
+
+    #[test]
+    fn non_regular_recursion() {
+        let source = "sum Nest<A> { Cons: Nest<(A, A)>, Nil }";
+        assert_eq!(
+            error(source),
+            "the member `Cons` of `Nest` holds `Nest` with type arguments other than type parameters, \
+            which would give it infinitely many layouts",
+        );
+    }
+
+    #[test]
+    fn regular_recursion() {
+        let source = "
+            sum List<T> { Cons: { head: T, tail: List<T> }, Nil }
+            sum Rose<T> { Node: (T, Forest<T>) }
+            sum Forest<U> { More: (Rose<U>, Forest<U>), Done }
+            fn start () -> () x { () }
+        ";
+        let (root, _) = loader::source("test.sn", source).expect("the source parses");
+        let mut program = referencer::resolve(&root).expect("the source resolves");
+        assert!(checker::check(&mut program).is_ok());
+    }
+}
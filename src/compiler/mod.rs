@@ -1,86 +1,1319 @@
-use std::{collections::HashMap, ops::Add};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use super::parser::{self, Ident};
+use super::referencer::{canonical, Program, Expression, Pattern, Statement, Type, TypeItemKind};
+use super::checker::Table;
+use super::loader::SourceMap;
+use super::vm::{self, Action, Layout, POINTER_LEN, SCOPE_ID_LEN};
 
-impl Function {
-    pub fn compile(&self, env: &Module) {
-        match self.expression {
-            Expression::Call { path, input } => {
-                env.function(path)
-            }
+#[derive(Clone, Copy)]
+enum Address {
+    Frame(isize),
+    Top(usize),
+}
+
+impl Address {
+    fn offset(self, by: usize) -> Self {
+        match self {
+            Self::Frame(at) => Self::Frame(at + by as isize),
+            Self::Top(at) => Self::Top(at - by),
+        }
+    }
+
+    fn below_top(self, frame_len: usize) -> usize {
+        match self {
+            Self::Frame(at) => (frame_len as isize - at) as usize,
+            Self::Top(at) => at,
         }
     }
 }
-use crate::parser::{Ident, Type};
 
+enum Op {
+    Copy {
+        from: Address,
+        to: Address,
+        len: usize,
+    },
+    Call(usize),
+    /// Calls the scope whose id is stored at `from`.
+    CallIndirect {
+        from: Address,
+    },
+    Match {
+        left: Address,
+        right: Address,
+        len: usize,
+        then: usize,
+        otherwise: usize,
+    },
+    Debug {
+        from: Address,
+        layout: Layout,
+        location: String,
+    },
+    Allocate {
+        len: usize,
+        to: Address,
+    },
+    Store {
+        from: Address,
+        pointer: Address,
+        len: usize,
+    },
+    Load {
+        pointer: Address,
+        to: Address,
+        len: usize,
+    },
+}
 
-impl<'a> Module<'a> {
-    pub fn path(&self, names: &[EnvIdent]) -> Option<&Module<'_>> {
-        let mut env = self;
-        for name in names {
-            if let Some(next_env) = match name {
-                EnvIdent::Sub(name) =>  self.modules.get(name),
-                EnvIdent::Super => env.sup
-            } {
-                env = next_env;
-            } else {
-                return None
-            }
+struct Scope {
+    frame: usize,
+    ops: Vec<Op>,
+}
+
+struct Frame {
+    data: Vec<u8>,
+    call: usize,
+    scope: usize,
+}
+
+struct Context {
+    function: usize,
+    frame: usize,
+    scope: usize,
+    locals: Vec<Option<Address>>,
+    substitution: HashMap<usize, Type>,
+}
+
+/// A generic function waiting to be compiled for one set of type arguments.
+struct Instance {
+    function: usize,
+    frame: usize,
+    substitution: HashMap<usize, Type>,
+}
+
+/// A part of the value being matched on, shared by every row that inspects it.
+#[derive(Clone)]
+struct Occurrence {
+    id: usize,
+    at: Address,
+    type_: Type,
+}
+
+/// What is left to test of one variant's pattern, the values it binds, and
+/// the guard to check once the tests pass.
+#[derive(Clone)]
+struct Row<'p> {
+    tests: Vec<(Occurrence, &'p Pattern)>,
+    bindings: Vec<(usize, Address)>,
+    guard: Option<(&'p Pattern, &'p Expression)>,
+    variant: usize,
+}
+
+pub struct Compiler<'a> {
+    program: &'a Program,
+    table: &'a Table,
+    sources: &'a SourceMap,
+    frames: Vec<Frame>,
+    scopes: Vec<Scope>,
+    instances: HashMap<(usize, Box<[Type]>), usize>,
+    pending: Vec<Instance>,
+    /// The layouts of boxed values, which `Layout::Boxed` refers to by index,
+    /// and the index each boxed type was given.
+    layouts: RefCell<Vec<Layout>>,
+    boxes: RefCell<HashMap<Type, usize>>,
+    occurrences: usize,
+}
+
+fn sorted<T>(value: &HashMap<Ident, T>) -> Vec<(&Ident, &T)> {
+    let mut value: Vec<_> = value.iter().collect();
+    value.sort_by_key(|&(name, _)| name);
+    value
+}
+
+impl<'a> Compiler<'a> {
+    /// The fields of a struct or record, or the variants of an enum or
+    /// anonymous variant, in layout order and with type arguments filled in.
+    fn members(&self, type_: &Type) -> Vec<(Ident, Type)> {
+        match type_ {
+            Type::Item(id, arguments) => match &self.program.types[*id].kind {
+                TypeItemKind::Struct(members) | TypeItemKind::Enum(members) => sorted(members).into_iter()
+                    .map(|(name, member)| (name.clone(), member.instantiate(arguments)))
+                    .collect(),
+                TypeItemKind::Alias(_) => unreachable!("aliases are expanded where they are named"),
+            },
+            Type::Record(members) | Type::Variant(members) => members.to_vec(),
+            Type::Tuple(_) | Type::Function(..) | Type::Number(_) | Type::Hole(_) | Type::Parameter(_) | Type::Alias(..) => unreachable!("checked item type"),
         }
+    }
 
-        Some(env)
+    fn is_enum(&self, type_: &Type) -> bool {
+        match type_ {
+            Type::Item(id, _) => matches!(self.program.types[*id].kind, TypeItemKind::Enum(_)),
+            Type::Variant(_) => true,
+            Type::Tuple(_) | Type::Record(_) | Type::Function(..) | Type::Number(_) | Type::Hole(_) | Type::Parameter(_) | Type::Alias(..) => false,
+        }
     }
 
-    pub fn function(&self, path: &[EnvIdent], name: &Ident) -> Option<&Function> {
-        self.path(path)?.functions.get(name)
+    /// Whether the member `name` of `type_` is stored behind a heap pointer.
+    fn is_boxed(&self, type_: &Type, name: &Ident) -> bool {
+        match type_ {
+            Type::Item(id, _) => self.table.boxed[*id].contains(name),
+            _ => false,
+        }
     }
 
-    pub fn type_(&self, path: &[EnvIdent], name: &Ident) -> Option<&Type> {
-        self.path(path)?.types.get(name)
+    fn member_size(&self, type_: &Type, name: &Ident, member: &Type) -> usize {
+        match self.is_boxed(type_, name) {
+            true => POINTER_LEN,
+            false => self.size(member),
+        }
     }
-}
 
-impl Module {
-    pub fn type_(&self, path: Path) -> Option<&Type> {
-        self.module_of_path_base(&normalize_path_base(path.base))?
-        .types.get(&path.name)
+    fn size(&self, type_: &Type) -> usize {
+        match type_ {
+            Type::Tuple(items) => items.iter().map(|item| self.size(item)).sum(),
+            Type::Function(..) => SCOPE_ID_LEN + POINTER_LEN,
+            Type::Number(number) => number_len(*number),
+            Type::Hole(_) => unreachable!("holes are solved by the checker"),
+            Type::Parameter(_) => unreachable!("parameters are instantiated with the members"),
+            Type::Alias(..) => unreachable!("aliases are expanded by the checker"),
+            _ if self.is_enum(type_) => 1 + self.members(type_).iter()
+                .map(|(name, variant)| self.member_size(type_, name, variant))
+                .max()
+                .unwrap_or(0),
+            _ => self.members(type_).iter().map(|(name, field)| self.member_size(type_, name, field)).sum(),
+        }
     }
 
-    pub fn function(&self, path: Path) -> Option<&Function> {
-        self.module_of_path_base(&normalize_path_base(path.base))?
-        .functions.get(&path.name)
+    /// The index of `type_` in the table of boxed layouts, adding it on first use.
+    fn boxed_layout(&self, type_: &Type) -> usize {
+        if let Some(index) = self.boxes.borrow().get(type_) {
+            return *index;
+        }
+
+        let index = self.layouts.borrow().len();
+        self.layouts.borrow_mut().push(Layout::Tuple(Box::default()));
+        self.boxes.borrow_mut().insert(type_.clone(), index);
+
+        let layout = self.layout(type_);
+        self.layouts.borrow_mut()[index] = layout;
+        index
     }
 
-    pub fn module(&self, path: Path) -> Option<&Module> {
-        self.module_of_path_base(&normalize_path_base(path.base))?
-        .modules.get(&path.name)
+    fn layout(&self, type_: &Type) -> Layout {
+        let (name, case): (_, fn(&Ident) -> String) = match type_ {
+            Type::Item(id, _) => (Some(self.program.types[*id].name.to_pascal_case()), Ident::to_pascal_case),
+            Type::Record(_) | Type::Variant(_) => (None, Ident::to_string),
+            Type::Tuple(items) => return Layout::Tuple(items.iter().map(|item| self.layout(item)).collect()),
+            Type::Function(..) => return Layout::Function,
+            Type::Number(number) => return Layout::Number(number_len(*number)),
+            Type::Hole(_) => unreachable!("holes are solved by the checker"),
+            Type::Parameter(_) => unreachable!("parameters are instantiated with the members"),
+            Type::Alias(..) => unreachable!("aliases are expanded by the checker"),
+        };
+        let members = self.members(type_).iter()
+            .map(|(name, member)| (case(name), match self.is_boxed(type_, name) {
+                true => Layout::Boxed(self.boxed_layout(member)),
+                false => self.layout(member),
+            }))
+            .collect();
+
+        if self.is_enum(type_) {
+            Layout::Enum { name, variants: members }
+        } else {
+            Layout::Struct { name, fields: members }
+        }
     }
 
-    pub fn module_of_path_base(&self, value: &[Ident]) -> Option<&Module> {
-        let mut current = self;
-        for part in value {
-            if let Some(module) = current.modules.get(part) {
-                current = module;
-            } else {
-                return None
+    fn field(&self, type_: &Type, name: &Ident) -> (usize, Type) {
+        let mut offset = 0;
+        for (field_name, field) in self.members(type_) {
+            if field_name == *name {
+                return (offset, field);
             }
+            offset += self.member_size(type_, &field_name, &field);
         }
-        Some(current)
+        unreachable!("checked field name")
     }
 
-    pub fn compile_function(&self, value: Function) {
-        match value.expression {
-            Expression::Call { path, input } => {
-                self.module(path.base)?.
-                self.function(path)
-            }
+    fn variant(&self, type_: &Type, tag: &Ident) -> (u8, Type) {
+        self.members(type_).into_iter()
+            .enumerate()
+            .find(|(_, (variant_tag, _))| variant_tag == tag)
+            .map(|(index, (_, variant))| (index as u8, variant))
+            .expect("checked tag name")
+    }
+
+    fn local(&self, context: &Context, id: usize) -> Type {
+        self.table.locals[context.function][id].substitute(&context.substitution)
+    }
+
+    fn node(&self, context: &Context, id: usize) -> Type {
+        self.table.nodes[id].substitute(&context.substitution)
+    }
+
+    fn type_of(&self, context: &Context, value: &Expression) -> Type {
+        match value {
+            Expression::Mul { id, .. }
+            | Expression::Sum { id, .. }
+            | Expression::Call { id, .. }
+            | Expression::Match { id, .. }
+            | Expression::Member { id, .. }
+            | Expression::Variable { id, .. }
+            | Expression::Lambda { id, .. }
+            | Expression::Apply { id, .. }
+            | Expression::Function { id, .. }
+            | Expression::Number { id, .. } => self.node(context, *id),
+            Expression::Block { result, .. } => self.type_of(context, result),
+            Expression::Debug { expression, .. } => self.type_of(context, expression),
+            Expression::Tuple(items) => Type::Tuple(items.iter().map(|item| self.type_of(context, item)).collect()),
+            Expression::Record(fields) => Type::Record(canonical(
+                fields.iter()
+                    .map(|(name, value)| (name.clone(), self.type_of(context, value)))
+                    .collect()
+            )),
+            Expression::Variant { id, .. } => self.node(context, *id),
+        }
+    }
+
+    fn alloc(&mut self, context: &Context, len: usize) -> Address {
+        let data = &mut self.frames[context.frame].data;
+        let at = data.len();
+        data.resize(at + len, 0);
+        Address::Frame(at as isize)
+    }
+
+    fn constant(&mut self, context: &Context, bytes: &[u8]) -> Address {
+        let data = &mut self.frames[context.frame].data;
+        let at = data.len();
+        data.extend(bytes);
+        Address::Frame(at as isize)
+    }
+
+    fn scope(&mut self, context: &Context) -> usize {
+        self.scopes.push(Scope { frame: context.frame, ops: Vec::new() });
+        self.scopes.len() - 1
+    }
+
+    fn emit(&mut self, context: &Context, op: Op) {
+        self.scopes[context.scope].ops.push(op);
+    }
+
+    /// The scope and signature of `function` as called from the node `id`,
+    /// compiling a new instance the first time a generic function meets a
+    /// set of type arguments.
+    fn callee(&mut self, context: &Context, id: usize, function: usize) -> (usize, Type, Type) {
+        let generic = &self.table.schemes[function];
+        let callee = &self.program.functions[function];
+        if generic.is_empty() {
+            return (function, callee.input.clone(), callee.output.clone());
         }
+
+        // Calls within the callee's own component are not instantiated by the
+        // checker, and use the holes of the signature as they stand.
+        let arguments: Box<[Type]> = match self.table.instances.get(&id) {
+            Some(instance) => instance.iter().map(|type_| type_.substitute(&context.substitution)).collect(),
+            None => generic.iter().map(|hole| Type::Hole(*hole).substitute(&context.substitution)).collect(),
+        };
+        let substitution: HashMap<usize, Type> = generic.iter().copied().zip(arguments.iter().cloned()).collect();
+        let (input, output) = (callee.input.substitute(&substitution), callee.output.substitute(&substitution));
+
+        let scope = match self.instances.get(&(function, arguments.clone())) {
+            Some(scope) => *scope,
+            None => {
+                let frame = self.frames.len();
+                let scope = self.scopes.len();
+                self.frames.push(Frame { data: Vec::new(), call: 0, scope });
+                self.scopes.push(Scope { frame, ops: Vec::new() });
+                self.instances.insert((function, arguments), scope);
+                self.pending.push(Instance { function, frame, substitution });
+                scope
+            },
+        };
+
+        (scope, input, output)
+    }
+
+    /// Evaluates `value` into a fresh heap block and stores its address at `to`.
+    fn boxed_expression(&mut self, context: &mut Context, value: &Expression, type_: &Type, to: Address) {
+        let len = self.size(type_);
+        let at = self.alloc(context, len);
+        self.expression(context, value, at);
+        self.emit(context, Op::Allocate { len, to });
+        self.emit(context, Op::Store { from: at, pointer: to, len });
+    }
+
+    fn copy(&mut self, context: &Context, from: Address, to: Address, len: usize) {
+        if len != 0 {
+            self.emit(context, Op::Copy { from, to, len });
+        }
+    }
+
+    fn expression(&mut self, context: &mut Context, value: &Expression, to: Address) {
+        match value {
+            Expression::Mul { id, base, fields, .. } => {
+                let type_ = self.node(context, *id);
+                if let Some(base) = base {
+                    self.expression(context, base, to);
+                }
+
+                for (name, value) in fields {
+                    let (offset, field) = self.field(&type_, name);
+                    match self.is_boxed(&type_, name) {
+                        true => self.boxed_expression(context, value, &field, to.offset(offset)),
+                        false => self.expression(context, value, to.offset(offset)),
+                    }
+                }
+            },
+
+            Expression::Sum { id, tag, body, .. } => {
+                let type_ = self.node(context, *id);
+                self.variant_expression(context, &type_, tag, body, to);
+            },
+
+            Expression::Record(fields) => {
+                let type_ = self.type_of(context, value);
+                for (name, value) in fields {
+                    let (offset, _) = self.field(&type_, name);
+                    self.expression(context, value, to.offset(offset));
+                }
+            },
+
+            Expression::Variant { id, tag, body } => {
+                let type_ = self.node(context, *id);
+                self.variant_expression(context, &type_, tag, body, to);
+            },
+
+            Expression::Call { id, function, input } => {
+                let (scope, input_type, output_type) = self.callee(context, *id, *function);
+                let input_len = self.size(&input_type);
+                let output_len = self.size(&output_type);
+
+                let at = self.alloc(context, input_len);
+                self.expression(context, input, at);
+                self.copy(context, at, Address::Top(input_len + output_len), input_len);
+                self.emit(context, Op::Call(scope));
+                self.copy(context, Address::Top(output_len), to, output_len);
+
+                let frame = &mut self.frames[context.frame];
+                frame.call = frame.call.max(input_len + output_len);
+            },
+
+            Expression::Apply { function, input, .. } => {
+                let Type::Function(input_type, output_type) = self.type_of(context, function) else {
+                    unreachable!("checked function type")
+                };
+                let input_len = self.size(&input_type);
+                let output_len = self.size(&output_type);
+
+                let closure = self.alloc(context, SCOPE_ID_LEN + POINTER_LEN);
+                self.expression(context, function, closure);
+                let at = self.alloc(context, input_len);
+                self.expression(context, input, at);
+                self.copy(context, closure.offset(SCOPE_ID_LEN), Address::Top(POINTER_LEN + input_len + output_len), POINTER_LEN);
+                self.copy(context, at, Address::Top(input_len + output_len), input_len);
+                self.emit(context, Op::CallIndirect { from: closure });
+                self.copy(context, Address::Top(output_len), to, output_len);
+
+                let frame = &mut self.frames[context.frame];
+                frame.call = frame.call.max(POINTER_LEN + input_len + output_len);
+            },
+
+            Expression::Function { id, function } => {
+                let (scope, ..) = self.callee(context, *id, *function);
+                self.closure(context, scope, &[], to);
+            },
+
+            Expression::Lambda { id, input, captures, body } => {
+                let scope = self.lambda(context, *id, *input, captures, body);
+                self.closure(context, scope, captures, to);
+            },
+
+            Expression::Match { on, variants, .. } => {
+                let type_ = self.type_of(context, on);
+                let at = self.alloc(context, self.size(&type_));
+                self.expression(context, on, at);
+                self.variants(context, at, &type_, variants, to);
+            },
+
+            Expression::Member { of, name, .. } => {
+                let type_ = self.type_of(context, of);
+                let (offset, field) = self.field(&type_, name);
+                let at = match self.place(context, of) {
+                    Some(at) => at,
+                    None => {
+                        let at = self.alloc(context, self.size(&type_));
+                        self.expression(context, of, at);
+                        at
+                    },
+                };
+                match self.is_boxed(&type_, name) {
+                    true => self.emit(context, Op::Load { pointer: at.offset(offset), to, len: self.size(&field) }),
+                    false => self.copy(context, at.offset(offset), to, self.size(&field)),
+                }
+            },
+
+            Expression::Variable { local, .. } => {
+                let from = context.locals[*local].expect("locals are bound before use");
+                self.copy(context, from, to, self.size(&self.local(context, *local)));
+            },
+
+            Expression::Block { statements, result } => {
+                for statement in statements.iter() {
+                    match statement {
+                        Statement::Let { local, value } => {
+                            let at = self.alloc(context, self.size(&self.local(context, *local)));
+                            self.expression(context, value, at);
+                            context.locals[*local] = Some(at);
+                        },
+                        Statement::Expression(value) => {
+                            let at = self.alloc(context, self.size(&self.type_of(context, value)));
+                            self.expression(context, value, at);
+                        },
+                    }
+                }
+
+                self.expression(context, result, to);
+            },
+
+            Expression::Debug { expression, from_end } => {
+                self.expression(context, expression, to);
+                let layout = self.layout(&self.type_of(context, expression));
+                let file = &self.sources.files[self.program.functions[context.function].file];
+                let location = format!("{}:{}", file.path.display(), parser::line_of(&file.source, *from_end));
+                self.emit(context, Op::Debug { from: to, layout, location });
+            },
+
+            Expression::Tuple(items) => {
+                let mut offset = 0;
+                for item in items.iter() {
+                    self.expression(context, item, to.offset(offset));
+                    offset += self.size(&self.type_of(context, item));
+                }
+            },
+
+            Expression::Number { id, value } => {
+                let len = self.size(&self.node(context, *id));
+                let constant = self.constant(context, &value.to_le_bytes()[..len]);
+                self.copy(context, constant, to, len);
+            },
+        }
+    }
+
+    /// Writes a closure calling `scope` to `to`, copying the `captures` into
+    /// a fresh heap block for it. Named functions capture nothing and ignore
+    /// the pointer.
+    fn closure(&mut self, context: &mut Context, scope: usize, captures: &[usize], to: Address) {
+        let mut bytes = scope.to_le_bytes().to_vec();
+        bytes.resize(SCOPE_ID_LEN + POINTER_LEN, 0);
+        let constant = self.constant(context, &bytes);
+        self.copy(context, constant, to, bytes.len());
+
+        if captures.is_empty() {
+            return;
+        }
+
+        let sizes: Vec<usize> = captures.iter().map(|&local| self.size(&self.local(context, local))).collect();
+        let len = sizes.iter().sum();
+        let at = self.alloc(context, len);
+        let mut offset = 0;
+        for (&local, size) in captures.iter().zip(sizes) {
+            let from = context.locals[local].expect("captured locals are bound before the lambda");
+            self.copy(context, from, at.offset(offset), size);
+            offset += size;
+        }
+
+        let pointer = to.offset(SCOPE_ID_LEN);
+        self.emit(context, Op::Allocate { len, to: pointer });
+        self.emit(context, Op::Store { from: at, pointer, len });
+    }
+
+    /// Compiles the body of a lambda into a scope of its own and returns it.
+    /// The body finds its captures by loading them from the pointer the
+    /// caller leaves below its input.
+    fn lambda(&mut self, context: &Context, id: usize, input: usize, captures: &[usize], body: &Expression) -> usize {
+        let Type::Function(input_type, output_type) = self.node(context, id) else {
+            unreachable!("checked lambda type")
+        };
+        let input_len = self.size(&input_type);
+        let output_len = self.size(&output_type);
+
+        let frame = self.frames.len();
+        let scope = self.scopes.len();
+        self.frames.push(Frame { data: Vec::new(), call: 0, scope });
+        self.scopes.push(Scope { frame, ops: Vec::new() });
+
+        let mut inner = Context {
+            function: context.function,
+            frame,
+            scope,
+            locals: vec![None; context.locals.len()],
+            substitution: context.substitution.clone(),
+        };
+        inner.locals[input] = Some(Address::Frame(-((input_len + output_len) as isize)));
+
+        let sizes: Vec<usize> = captures.iter().map(|&local| self.size(&self.local(context, local))).collect();
+        let len = sizes.iter().sum();
+        let at = self.alloc(&inner, len);
+        if len != 0 {
+            let pointer = Address::Frame(-((POINTER_LEN + input_len + output_len) as isize));
+            self.emit(&inner, Op::Load { pointer, to: at, len });
+        }
+
+        let mut offset = 0;
+        for (&local, size) in captures.iter().zip(sizes) {
+            inner.locals[local] = Some(at.offset(offset));
+            offset += size;
+        }
+
+        self.expression(&mut inner, body, Address::Frame(-(output_len as isize)));
+        scope
+    }
+
+    /// Where `value` already lives when it names a local or a field of one,
+    /// letting member access read it in place.
+    fn place(&self, context: &Context, value: &Expression) -> Option<Address> {
+        match value {
+            Expression::Variable { local, .. } => context.locals[*local],
+            Expression::Member { of, name, .. } => {
+                let type_ = self.type_of(context, of);
+                if self.is_boxed(&type_, name) {
+                    return None;
+                }
+                let (offset, _) = self.field(&type_, name);
+                Some(self.place(context, of)?.offset(offset))
+            },
+            _ => None,
+        }
+    }
+
+    fn variant_expression(&mut self, context: &mut Context, type_: &Type, tag: &Ident, body: &Expression, to: Address) {
+        let (index, variant) = self.variant(type_, tag);
+        let mut bytes = vec![0; self.size(type_)];
+        bytes[0] = index;
+
+        let constant = self.constant(context, &bytes);
+        self.copy(context, constant, to, bytes.len());
+        match self.is_boxed(type_, tag) {
+            true => self.boxed_expression(context, body, &variant, to.offset(1)),
+            false => self.expression(context, body, to.offset(1)),
+        }
+    }
+
+    fn occurrence(&mut self, at: Address, type_: Type) -> Occurrence {
+        self.occurrences += 1;
+        Occurrence { id: self.occurrences, at, type_ }
+    }
+
+    /// The member `name` of `of` at `offset`, loaded into a temporary first
+    /// when it is boxed.
+    fn member(&mut self, context: &Context, of: &Occurrence, name: &Ident, offset: usize, type_: Type) -> Occurrence {
+        let at = of.at.offset(offset);
+        if !self.is_boxed(&of.type_, name) {
+            return self.occurrence(at, type_);
+        }
+
+        let len = self.size(&type_);
+        let to = self.alloc(context, len);
+        self.emit(context, Op::Load { pointer: at, to, len });
+        self.occurrence(to, type_)
     }
 
+    /// Compiles a match into a decision tree. Each variant's value is
+    /// compiled once into a scope of its own, which every leaf reaching it
+    /// calls after copying the pattern's bindings into their locals.
+    fn variants(&mut self, context: &mut Context, on: Address, type_: &Type, variants: &[(Pattern, Expression)], to: Address) {
+        let scope = context.scope;
 
-    pub fn compile(self) {
-        for (name, value) in self.functions {
-            if value.is_external {
-                value.expression
+        let mut bound = Vec::new();
+        for (pattern, _) in variants {
+            pattern_locals(pattern, &mut bound);
+        }
+        for local in bound {
+            let at = self.alloc(context, self.size(&self.local(context, local)));
+            context.locals[local] = Some(at);
+        }
+
+        let values: Vec<usize> = variants.iter()
+            .map(|(_, value)| {
+                context.scope = self.scope(context);
+                self.expression(context, value, to);
+                context.scope
+            })
+            .collect();
+        context.scope = scope;
+
+        let on = self.occurrence(on, type_.clone());
+        let rows = variants.iter().enumerate()
+            .map(|(variant, (pattern, _))| Row { tests: vec![(on.clone(), pattern)], bindings: Vec::new(), guard: None, variant })
+            .collect();
+        self.tree(context, rows, &values);
+        context.scope = scope;
+    }
+
+    /// Emits the tests that pick the first of `rows` matching, into the
+    /// current scope, testing the first thing the first row still needs.
+    fn tree<'p>(&mut self, context: &mut Context, rows: Vec<Row<'p>>, values: &[usize]) {
+        let mut split = Vec::new();
+        for row in rows {
+            simplify(row, &mut split);
+        }
+        let mut rows = split;
+
+        // Only a match the checker found exhaustive gets here, so some row
+        // always matches and running out of rows cannot happen at runtime.
+        let Some(first) = rows.first() else { return };
+        let Some((occurrence, pattern)) = first.tests.first().cloned() else {
+            for &(local, at) in &first.bindings {
+                let to = context.locals[local].expect("pattern locals have a place");
+                self.copy(context, at, to, self.size(&self.local(context, local)));
+            }
+
+            match first.guard {
+                Some((guard, on)) => {
+                    let type_ = self.type_of(context, on);
+                    let at = self.alloc(context, self.size(&type_));
+                    self.expression(context, on, at);
+
+                    let occurrence = self.occurrence(at, type_);
+                    rows[0] = Row { tests: vec![(occurrence, guard)], bindings: Vec::new(), guard: None, variant: first.variant };
+                    self.tree(context, rows, values);
+                },
+                None => self.emit(context, Op::Call(values[first.variant])),
             }
+            return
+        };
+
+        match pattern {
+            Pattern::Tuple(_) | Pattern::Fields(_) => {
+                self.expand(context, &occurrence, &mut rows);
+                self.tree(context, rows, values);
+            },
+
+            Pattern::Tag { .. } if !self.is_enum(&occurrence.type_) => {
+                self.expand(context, &occurrence, &mut rows);
+                self.tree(context, rows, values);
+            },
+
+            Pattern::Tag { .. } => {
+                let mut tags: Vec<&Ident> = Vec::new();
+                for row in &rows {
+                    for (other, pattern) in &row.tests {
+                        if let (true, Pattern::Tag { tag, .. }) = (other.id == occurrence.id, pattern) {
+                            if !tags.contains(&tag) {
+                                tags.push(tag);
+                            }
+                        }
+                    }
+                }
+                let is_complete = tags.len() == self.members(&occurrence.type_).len();
+
+                let scope = context.scope;
+                for (index, tag) in tags.iter().enumerate() {
+                    let is_last = is_complete && index == tags.len() - 1;
+                    let (variant_index, variant) = self.variant(&occurrence.type_, tag);
+
+                    let otherwise = if is_last {
+                        None
+                    } else {
+                        let right = self.constant(context, &[variant_index]);
+                        let then = self.scope(context);
+                        let otherwise = self.scope(context);
+                        self.emit(context, Op::Match { left: occurrence.at, right, len: 1, then, otherwise });
+                        context.scope = then;
+                        Some(otherwise)
+                    };
+
+                    let body = self.member(context, &occurrence, tag, 1, variant);
+                    let rows = rows.iter()
+                        .filter_map(|row| {
+                            let mut row = row.clone();
+                            if let Some(position) = row.tests.iter().position(|(other, _)| other.id == occurrence.id) {
+                                match row.tests[position].1 {
+                                    Pattern::Tag { tag: other, body: pattern, .. } if other == *tag => row.tests[position] = (body.clone(), pattern),
+                                    Pattern::Tag { .. } => return None,
+                                    _ => {},
+                                }
+                            }
+                            Some(row)
+                        })
+                        .collect();
+                    self.tree(context, rows, values);
+
+                    if let Some(otherwise) = otherwise {
+                        context.scope = otherwise;
+                    }
+                }
+
+                if !is_complete {
+                    let rows = rows.into_iter()
+                        .filter(|row| !row.tests.iter().any(|(other, pattern)| other.id == occurrence.id && matches!(pattern, Pattern::Tag { .. })))
+                        .collect();
+                    self.tree(context, rows, values);
+                }
+                context.scope = scope;
+            },
+
+            Pattern::Equals(expression) => {
+                let len = self.size(&occurrence.type_);
+                let right = self.alloc(context, len);
+                self.expression(context, expression, right);
+
+                let scope = context.scope;
+                let then = self.scope(context);
+                let otherwise = self.scope(context);
+                self.emit(context, Op::Match { left: occurrence.at, right, len, then, otherwise });
+
+                let mut matched = rows.clone();
+                matched[0].tests.remove(0);
+                context.scope = then;
+                self.tree(context, matched, values);
+
+                rows.remove(0);
+                context.scope = otherwise;
+                self.tree(context, rows, values);
+                context.scope = scope;
+            },
+
+            Pattern::Any(_) | Pattern::Wildcard | Pattern::Or(_) | Pattern::Bind(..) | Pattern::Guard { .. } => {
+                unreachable!("rows are simplified first")
+            },
+        }
+    }
+
+    /// Replaces every test of the tuple, struct or record at `occurrence`
+    /// with tests of its members.
+    fn expand<'p>(&mut self, context: &Context, occurrence: &Occurrence, rows: &mut [Row<'p>]) {
+        let mut members: Vec<(Ident, Occurrence)> = Vec::new();
+        let mut items: Option<Vec<Occurrence>> = None;
+
+        for row in rows.iter_mut() {
+            let Some(position) = row.tests.iter().position(|(other, _)| other.id == occurrence.id) else {
+                continue
+            };
+
+            // A tag on a product can only be a pattern naming the `mul` the
+            // checker found the value to be, in front of its fields.
+            let pattern = match row.tests[position].1 {
+                Pattern::Tag { body, .. } => &**body,
+                pattern => pattern,
+            };
+            let tests: Vec<(Occurrence, &'p Pattern)> = match pattern {
+                Pattern::Tuple(patterns) => {
+                    let items = items.get_or_insert_with(|| {
+                        let Type::Tuple(types) = &occurrence.type_ else {
+                            unreachable!("checked tuple pattern")
+                        };
+                        let mut offset = 0;
+                        types.iter()
+                            .map(|type_| {
+                                let item = self.occurrence(occurrence.at.offset(offset), type_.clone());
+                                offset += self.size(type_);
+                                item
+                            })
+                            .collect()
+                    });
+                    items.iter().cloned().zip(patterns.iter()).collect()
+                },
+                Pattern::Fields(fields) => sorted(fields).into_iter()
+                    .map(|(name, pattern)| {
+                        let member = match members.iter().find(|(other, _)| other == name) {
+                            Some((_, member)) => member.clone(),
+                            None => {
+                                let (offset, type_) = self.field(&occurrence.type_, name);
+                                let member = self.member(context, occurrence, name, offset, type_);
+                                members.push((name.clone(), member.clone()));
+                                member
+                            },
+                        };
+                        (member, pattern)
+                    })
+                    .collect(),
+                _ => unreachable!("checked product pattern"),
+            };
+            row.tests.splice(position..position + 1, tests);
+        }
+    }
+
+    fn finish(self) -> (Vec<vm::Scope>, Box<[Layout]>) {
+        let scopes = self.scopes.into_iter()
+            .enumerate()
+            .map(|(id, scope)| {
+                let frame = &self.frames[scope.frame];
+                let len = frame.data.len() + frame.call;
+                let from = |at: Address, size: usize| at.below_top(len) - size;
+
+                let actions = scope.ops.into_iter()
+                    .map(|op| match op {
+                        Op::Copy { from: at, to, len: size } => Action::Copy { from: from(at, size), to: to.below_top(len), len: size },
+                        Op::Call(id) => Action::Call(id),
+                        Op::CallIndirect { from: at } => Action::CallIndirect { from: from(at, SCOPE_ID_LEN) },
+                        Op::Match { left, right, len: size, then, otherwise } => Action::Match {
+                            left: from(left, size),
+                            right: from(right, size),
+                            len: size,
+                            then,
+                            otherwise,
+                        },
+                        Op::Debug { from: at, layout, location } => Action::Debug { from: from(at, layout.len()), layout, location },
+                        Op::Allocate { len: size, to } => Action::Allocate { len: size, to: to.below_top(len) },
+                        Op::Store { from: at, pointer, len: size } => Action::Store {
+                            from: from(at, size),
+                            pointer: from(pointer, POINTER_LEN),
+                            len: size,
+                        },
+                        Op::Load { pointer, to, len: size } => Action::Load {
+                            pointer: from(pointer, POINTER_LEN),
+                            to: to.below_top(len),
+                            len: size,
+                        },
+                    })
+                    .collect();
+
+                let data = if id == frame.scope {
+                    let mut data = frame.data.clone();
+                    data.resize(len, 0);
+                    data.into()
+                } else {
+                    Box::default()
+                };
+
+                vm::Scope { data, actions }
+            })
+            .collect();
+
+        (scopes, self.layouts.into_inner().into())
+    }
+
+    fn function(&mut self, id: usize, frame: usize, substitution: HashMap<usize, Type>) {
+        let function = &self.program.functions[id];
+        let input_len = self.size(&function.input.substitute(&substitution));
+        let output_len = self.size(&function.output.substitute(&substitution));
+
+        let mut context = Context {
+            function: id,
+            frame,
+            scope: self.frames[frame].scope,
+            locals: vec![None; function.locals],
+            substitution,
+        };
+        context.locals[0] = Some(Address::Frame(-((input_len + output_len) as isize)));
+        self.expression(&mut context, &function.expression, Address::Frame(-(output_len as isize)));
+    }
+}
+
+/// Function `i` compiles to scope `i`. Generic functions leave their scope
+/// empty and get a further scope per set of type arguments they are called with.
+/// Also returns the layouts that `Layout::Boxed` values point to.
+pub fn compile(program: &Program, table: &Table, sources: &SourceMap) -> (Vec<vm::Scope>, Box<[Layout]>) {
+    let mut compiler = Compiler {
+        program,
+        table,
+        sources,
+        frames: Vec::new(),
+        scopes: Vec::new(),
+        instances: HashMap::new(),
+        pending: Vec::new(),
+        layouts: RefCell::new(Vec::new()),
+        boxes: RefCell::new(HashMap::new()),
+        occurrences: 0,
+    };
+
+    for id in 0..program.functions.len() {
+        compiler.frames.push(Frame { data: Vec::new(), call: 0, scope: id });
+        compiler.scopes.push(Scope { frame: id, ops: Vec::new() });
+    }
+
+    for id in 0..program.functions.len() {
+        if table.schemes[id].is_empty() {
+            compiler.function(id, id, HashMap::new());
+        }
+    }
+
+    while let Some(Instance { function, frame, substitution }) = compiler.pending.pop() {
+        compiler.function(function, frame, substitution);
+    }
+
+    compiler.finish()
+}
+
+/// How many bytes a value of the number type `number` takes: enough for
+/// every number below it.
+fn number_len(number: u64) -> usize {
+    let max = number.saturating_sub(1);
+    (u64::BITS - max.leading_zeros()).div_ceil(8) as usize
+}
+
+/// Takes the bindings, wildcards and guard out of the tests of `row`, and
+/// splits it into a row per alternative of each or-pattern, in order.
+fn simplify<'p>(mut row: Row<'p>, rows: &mut Vec<Row<'p>>) {
+    let mut index = 0;
+    while let Some((occurrence, pattern)) = row.tests.get(index).cloned() {
+        match pattern {
+            Pattern::Any(local) => {
+                row.bindings.push((*local, occurrence.at));
+                row.tests.remove(index);
+            },
+            Pattern::Wildcard => {
+                row.tests.remove(index);
+            },
+            Pattern::Bind(local, pattern) => {
+                row.bindings.push((*local, occurrence.at));
+                row.tests[index].1 = pattern;
+            },
+            Pattern::Guard { pattern, guard, on } => {
+                row.guard = Some((guard, on));
+                row.tests[index].1 = pattern;
+            },
+            Pattern::Or(alternatives) => {
+                for alternative in alternatives.iter() {
+                    let mut row = row.clone();
+                    row.tests[index].1 = alternative;
+                    simplify(row, rows);
+                }
+                return
+            },
+            _ => index += 1,
         }
     }
+    rows.push(row);
+}
+
+/// The locals `value` binds, each once.
+fn pattern_locals(value: &Pattern, locals: &mut Vec<usize>) {
+    match value {
+        Pattern::Any(local) => if !locals.contains(local) {
+            locals.push(*local);
+        },
+        Pattern::Tag { body, .. } => pattern_locals(body, locals),
+        Pattern::Fields(fields) => fields.values().for_each(|value| pattern_locals(value, locals)),
+        Pattern::Tuple(items) | Pattern::Or(items) => items.iter().for_each(|item| pattern_locals(item, locals)),
+        Pattern::Bind(local, pattern) => {
+            if !locals.contains(local) {
+                locals.push(*local);
+            }
+            pattern_locals(pattern, locals);
+        },
+        Pattern::Guard { pattern, guard, .. } => {
+            pattern_locals(pattern, locals);
+            pattern_locals(guard, locals);
+        },
+        Pattern::Equals(_) | Pattern::Wildcard => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{checker, loader, referencer, vm::Vm};
+
+    /// Compiles and runs the `start` of `source`, giving back every value
+    /// its `dbg!`s print, without their locations.
+    fn run(source: &str) -> Vec<String> {
+        let (root, sources) = loader::source("test.sn", source).unwrap_or_else(|error| panic!("{error}"));
+        let mut program = referencer::resolve(&root).unwrap_or_else(|error| panic!("{}", error.error));
+        let table = checker::check(&mut program).unwrap_or_else(|error| panic!("{}", program.named(&error)));
+        let (scopes, layouts) = super::compile(&program, &table, &sources);
+
+        let mut out = Vec::new();
+        Vm::with_output(layouts, &mut out).run(program.entry.expect("a start function"), &[], 0, &scopes);
+        String::from_utf8(out).unwrap().lines()
+            .map(|line| line.split_once(": ").expect("a location").1.to_string())
+            .collect()
+    }
+
+    const COLORS: &str = "
+        sum Bool { True, False }
+        sum Color { Red, Green, Blue }
+        sum List<T> { Cons: { head: T, tail: List<T> }, Nil }
+    ";
+
+    #[test]
+    fn first_matching_variant() {
+        let source = format!("{COLORS}
+            fn classify (Color, Color) -> (Bool, Color) p {{
+                match p {{
+                    (Red _, c) -> (Bool:True (), c),
+                    (c, Red _) -> (Bool:False (), c),
+                    (Green _, Green _) -> (Bool:True (), Color:Blue ()),
+                    (x, y) -> (Bool:False (), y),
+                }}
+            }}
+
+            fn start () -> () a {{
+                dbg! classify (Color:Red (), Color:Red ());
+                dbg! classify (Color:Blue (), Color:Red ());
+                dbg! classify (Color:Green (), Color:Green ());
+                dbg! classify (Color:Green (), Color:Blue ());
+            }}
+        ");
+        assert_eq!(run(&source), [
+            "(Bool:True (), Color:Red ())",
+            "(Bool:False (), Color:Blue ())",
+            "(Bool:True (), Color:Blue ())",
+            "(Bool:False (), Color:Blue ())",
+        ]);
+    }
+
+    #[test]
+    fn boxed_members() {
+        let source = format!("{COLORS}
+            fn second List<Color> -> Color l {{
+                match l {{
+                    Cons {{ tail = Cons {{ head = h }} }} -> h,
+                    Cons {{ head = h }} -> h,
+                    Nil _ -> Color:Red (),
+                }}
+            }}
+
+            fn start () -> () a {{
+                dbg! second (List:Cons {{ head = Color:Green (), tail = List:Cons {{ head = Color:Blue (), tail = List:Nil () }} }});
+                dbg! second (List:Cons {{ head = Color:Green (), tail = List:Nil () }});
+                dbg! second (List:Nil ());
+            }}
+        ");
+        assert_eq!(run(&source), ["Color:Blue ()", "Color:Green ()", "Color:Red ()"]);
+    }
+
+    #[test]
+    fn recursion_through_boxes() {
+        let source = format!("{COLORS}
+            fn last List<Color> -> Color l {{
+                match l {{
+                    Cons {{ head = h, tail = Nil _ }} -> h,
+                    Cons {{ tail = t }} -> last t,
+                    Nil _ -> Color:Red (),
+                }}
+            }}
+
+            fn start () -> () a {{
+                let list = List:Cons {{ head = Color:Green (), tail = List:Cons {{ head = Color:Blue (), tail = List:Nil () }} }};
+                dbg! last list;
+                dbg! List:Cons {{ head = Color:Red (), tail = list }};
+            }}
+        ");
+        assert_eq!(run(&source), [
+            "Color:Blue ()",
+            "List:Cons { head: Color:Red (), tail: List:Cons { head: Color:Green (), tail: List:Cons { head: Color:Blue (), tail: List:Nil () } } }",
+        ]);
+    }
+
+    #[test]
+    fn alternatives() {
+        let source = format!("{COLORS}
+            fn warm (Color, Bool) -> Bool p {{
+                match p {{
+                    (Red _, _) | (Green _, True _) -> Bool:True (),
+                    _ -> Bool:False (),
+                }}
+            }}
+
+            fn start () -> () a {{
+                dbg! warm (Color:Red (), Bool:False ());
+                dbg! warm (Color:Green (), Bool:True ());
+                dbg! warm (Color:Green (), Bool:False ());
+                dbg! warm (Color:Blue (), Bool:True ());
+            }}
+        ");
+        assert_eq!(run(&source), ["Bool:True ()", "Bool:True ()", "Bool:False ()", "Bool:False ()"]);
+    }
+
+    #[test]
+    fn alternatives_binding() {
+        let source = format!("{COLORS}
+            fn other (Color, Color) -> Color p {{
+                match p {{
+                    (Red _, c) | (c, Red _) -> c,
+                    (c, _) -> c,
+                }}
+            }}
+
+            fn start () -> () a {{
+                dbg! other (Color:Red (), Color:Blue ());
+                dbg! other (Color:Green (), Color:Red ());
+                dbg! other (Color:Blue (), Color:Green ());
+            }}
+        ");
+        assert_eq!(run(&source), ["Color:Blue ()", "Color:Green ()", "Color:Blue ()"]);
+    }
+
+    #[test]
+    fn guards_fall_through() {
+        let source = format!("{COLORS}
+            fn is_red Color -> Bool c {{ match c {{ Red _ -> Bool:True (), _ -> Bool:False () }} }}
+
+            fn pick (Color, Bool) -> Color p {{
+                match p {{
+                    (c, True _) if is_red c -> Color:Green (),
+                    (c, b) if True _ = b -> c,
+                    (Blue _, _) -> Color:Red (),
+                    _ -> Color:Blue (),
+                }}
+            }}
+
+            fn start () -> () a {{
+                dbg! pick (Color:Red (), Bool:True ());
+                dbg! pick (Color:Green (), Bool:True ());
+                dbg! pick (Color:Blue (), Bool:False ());
+                dbg! pick (Color:Red (), Bool:False ());
+            }}
+        ");
+        assert_eq!(run(&source), ["Color:Green ()", "Color:Green ()", "Color:Red ()", "Color:Blue ()"]);
+    }
+
+    #[test]
+    fn bindings_of_whole_values() {
+        let source = format!("{COLORS}
+            fn head List<Color> -> (List<Color>, Color) l {{
+                match l {{
+                    whole @ Cons {{ head = c @ (Red _ | Green _) }} -> (whole, c),
+                    _ -> (List:Nil (), Color:Blue ()),
+                }}
+            }}
+
+            fn start () -> () a {{
+                dbg! head (List:Cons {{ head = Color:Green (), tail = List:Nil () }});
+                dbg! head (List:Cons {{ head = Color:Blue (), tail = List:Nil () }});
+            }}
+        ");
+        assert_eq!(run(&source), [
+            "(List:Cons { head: Color:Green (), tail: List:Nil () }, Color:Green ())",
+            "(List:Nil (), Color:Blue ())",
+        ]);
+    }
+
+    #[test]
+    fn closures() {
+        let source = format!("{COLORS}
+            fn apply (Color -> Color, Color) -> Color p {{ match p {{ (f, c) -> f c }} }}
+
+            fn start () -> () a {{
+                let fallback = Color:Green ();
+                let swap = fn c {{ match c {{ Red _ -> fallback, _ -> Color:Red () }} }};
+                dbg! apply (swap, Color:Red ());
+                dbg! apply (swap, Color:Blue ());
+                dbg! swap fallback;
+            }}
+        ");
+        assert_eq!(run(&source), ["Color:Green ()", "Color:Red ()", "Color:Red ()"]);
+    }
+
+    #[test]
+    fn functions_as_values() {
+        let source = format!("{COLORS}
+            fn red Color -> Color c {{ Color:Red () }}
+            fn twice (Color -> Color) -> Color -> Color f {{ fn c {{ f (f c) }} }}
+            fn constant Color -> Color -> Color c {{ fn x {{ c }} }}
+
+            fn start () -> () a {{
+                let f = twice red;
+                dbg! f Color:Blue ();
+                let f = twice (constant Color:Green ());
+                dbg! f Color:Red ();
+                let f = twice (fn c {{ match c {{ Red _ -> Color:Green (), _ -> Color:Blue () }} }});
+                dbg! f Color:Red ();
+            }}
+        ");
+        assert_eq!(run(&source), ["Color:Red ()", "Color:Green ()", "Color:Blue ()"]);
+    }
+
+    #[test]
+    fn lets() {
+        let source = format!("{COLORS}
+            sum Option<T> {{ Some: T, None }}
+            mul Pair {{ Left: Color, Right: (Color, Bool) }}
+
+            fn none () -> Option<Color> a {{ Option:None () }}
+
+            fn start () -> () a {{
+                let pair = Pair {{ Left = Color:Red (), Right = (Color:Green (), Bool:True ()) }};
+                let Pair {{ Left = l }} = pair;
+                dbg! l;
+                let {{ Right = (c, _) }} = pair;
+                dbg! c;
+                let (x, y) = (Color:Blue (), l);
+                dbg! (y, x);
+                let Some s = Option:Some Color:Blue () else {{ dbg! (); }};
+                dbg! s;
+                let Some t = none () else {{ dbg! Bool:False (); }};
+                dbg! t;
+            }}
+        ");
+        assert_eq!(run(&source), ["Color:Red ()", "Color:Green ()", "(Color:Red (), Color:Blue ())", "Color:Blue ()", "Bool:False ()"]);
+    }
+
+    #[test]
+    fn let_else_leaves_the_block() {
+        let source = format!("{COLORS}
+            sum Option<T> {{ Some: T, None }}
+
+            fn or_red Option<Color> -> Color o {{
+                let Some c = o else Color:Red ();
+                dbg! c;
+                Color:Blue ()
+            }}
+
+            fn start () -> () a {{
+                dbg! or_red (Option:Some Color:Green ());
+                dbg! or_red (Option:None ());
+            }}
+        ");
+        assert_eq!(run(&source), ["Color:Green ()", "Color:Blue ()", "Color:Red ()"]);
+    }
+
+    #[test]
+    fn mul_named_tag() {
+        let source = format!("{COLORS}
+            mul Apple {{ Size: Color }}
+            sum Fruit {{ Apple: Apple, Other }}
+
+            fn size Fruit -> Color f {{
+                match f {{ Apple {{ Size = s }} -> s, Other _ -> Color:Red () }}
+            }}
+
+            fn start () -> () a {{
+                dbg! size (Fruit:Apple Apple {{ Size = Color:Blue () }});
+                dbg! size (Fruit:Other ());
+                let Apple {{ Size = s }} = Apple {{ Size = Color:Green () }};
+                dbg! s;
+            }}
+        ");
+        assert_eq!(run(&source), ["Color:Blue ()", "Color:Red ()", "Color:Green ()"]);
+    }
+
+    #[test]
+    fn record_update() {
+        let source = format!("{COLORS}
+            mul Pair {{ Left: Color, Right: Color }}
+
+            fn start () -> () a {{
+                let pair = Pair {{ Left = Color:Red (), Right = Color:Green () }};
+                dbg! Pair {{ .. pair, Left = Color:Blue () }};
+                dbg! (Pair {{..pair}}).Right;
+            }}
+        ");
+        assert_eq!(run(&source), ["Pair { Left: Color:Blue (), Right: Color:Green () }", "Color:Green ()"]);
+    }
+
+    #[test]
+    fn numbers() {
+        let source = "
+            fn name 300 -> 300 n {
+                match n {
+                    0 -> 10,
+                    ~256 -> 20,
+                    m -> m,
+                }
+            }
+
+            fn start () -> () a {
+                dbg! name 0;
+                dbg! name 256;
+                dbg! name 7;
+                dbg! (name 299, 1);
+            }
+        ";
+        assert_eq!(run(source), ["10", "20", "7", "(299, 1)"]);
+    }
 }
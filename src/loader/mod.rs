@@ -0,0 +1,269 @@
+use std::{fmt, fs, io, path::{Path, PathBuf}};
+use super::parser::{self, Ident, ItemError, ItemFromCharsPrefixError};
+
+pub struct File {
+    pub path: PathBuf,
+    pub source: Box<[char]>,
+}
+
+#[derive(Default)]
+pub struct SourceMap {
+    pub files: Vec<File>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Read {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        line: usize,
+        error: ItemFromCharsPrefixError,
+    },
+    MissingModule {
+        name: Ident,
+        candidates: [PathBuf; 2],
+    },
+    AmbiguousModule {
+        name: Ident,
+        candidates: [PathBuf; 2],
+    },
+    Cycle(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read { path, error } => write!(f, "cannot read {}: {error}", path.display()),
+            Self::Parse { path, line, error } => write!(f, "{}:{line}: {error}", path.display()),
+            Self::MissingModule { name, candidates: [file, dir] } => {
+                write!(f, "no file for module `{name}`, looked for {} and {}", file.display(), dir.display())
+            },
+            Self::AmbiguousModule { name, candidates: [file, dir] } => {
+                write!(f, "module `{name}` has two files, {} and {}", file.display(), dir.display())
+            },
+            Self::Cycle(path) => write!(f, "{} is loaded as a module of itself", path.display()),
+        }
+    }
+}
+
+fn parse(path: &Path, source: &[char]) -> Result<parser::Module, Error> {
+    parser::Module::from_chars(source).map_err(|ItemError { error, from_end }| Error::Parse {
+        path: path.to_path_buf(),
+        line: parser::line_of(source, from_end),
+        error,
+    })
+}
+
+struct Loader {
+    sources: SourceMap,
+    stack: Vec<PathBuf>,
+}
+
+impl Loader {
+    /// Loads the file at `path`. The root file and `mod.sn` files keep
+    /// their submodule files next to them, and any other file `name.sn`
+    /// keeps them in the directory `name`.
+    fn file(&mut self, path: PathBuf, is_root: bool) -> Result<parser::Module, Error> {
+        let canonical = fs::canonicalize(&path).map_err(|error| Error::Read { path: path.clone(), error })?;
+        if self.stack.contains(&canonical) {
+            return Err(Error::Cycle(path));
+        }
+
+        let source: Box<[char]> = fs::read_to_string(&path)
+            .map_err(|error| Error::Read { path: path.clone(), error })?
+            .chars()
+            .collect();
+        let mut module = parse(&path, &source)?;
+
+        let file = self.sources.files.len();
+        let mut dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        if let (false, Some(stem)) = (is_root, path.file_stem().filter(|stem| *stem != "mod")) {
+            dir.push(stem);
+        }
+        self.sources.files.push(File { path, source });
+
+        self.stack.push(canonical);
+        self.module(&mut module, file, &dir)?;
+        self.stack.pop();
+
+        Ok(module)
+    }
+
+    /// Marks `module` and its inline submodules as coming from `file`, and
+    /// loads every `mod name;` they declare from `dir`, or from the
+    /// directory named after an inline submodule for the ones inside it.
+    fn module(&mut self, module: &mut parser::Module, file: usize, dir: &Path) -> Result<(), Error> {
+        module.file = file;
+
+        for (name, sub) in module.modules.iter_mut() {
+            self.module(sub, file, &dir.join(name.to_string()))?;
+        }
+
+        for (name, visibility) in std::mem::take(&mut module.files) {
+            let candidates = [dir.join(format!("{name}.sn")), dir.join(name.to_string()).join("mod.sn")];
+            let path = match (candidates[0].is_file(), candidates[1].is_file()) {
+                (true, false) => candidates[0].clone(),
+                (false, true) => candidates[1].clone(),
+                (true, true) => return Err(Error::AmbiguousModule { name, candidates }),
+                (false, false) => return Err(Error::MissingModule { name, candidates }),
+            };
+
+            let mut sub = self.file(path, false)?;
+            sub.visibility = visibility;
+            module.modules.insert(name, sub);
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the file at `path` as the root module, along with every file its
+/// `mod name;` items pull in.
+pub fn load(path: &Path) -> Result<(parser::Module, SourceMap), Error> {
+    let mut loader = Loader {
+        sources: SourceMap::default(),
+        stack: Vec::new(),
+    };
+    let root = loader.file(path.to_path_buf(), true)?;
+
+    Ok((root, loader.sources))
+}
+
+/// Parses `source` as a root module named `path` without touching the file
+/// system, so it may not declare `mod name;` items.
+#[cfg(test)]
+pub fn source(path: &str, source: &str) -> Result<(parser::Module, SourceMap), Error> {
+    let path = PathBuf::from(path);
+    let source: Box<[char]> = source.chars().collect();
+    let module = parse(&path, &source)?;
+
+    Ok((module, SourceMap { files: vec![File { path, source }] }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(src: &str) -> String {
+        match source("test.sn", src) {
+            Ok(_) => panic!("expected an error"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn duplicates() {
+        assert_eq!(
+            error("fn f () -> () x { () } fn g () -> () x { () } fn g = f;"),
+            "test.sn:1: `g` is defined more than once in this module",
+        );
+        assert_eq!(
+            error("sum B { X, Y } sum B { Z }"),
+            "test.sn:1: `B` is defined more than once in this module",
+        );
+        assert_eq!(
+            error("g :: () -> () g :: () -> ()"),
+            "test.sn:1: `g` is defined more than once in this module",
+        );
+        assert_eq!(
+            error("mod m { } mod m { }"),
+            "test.sn:1: `m` is defined more than once in this module",
+        );
+        assert!(source("test.sn", "fn g () -> () x { () } sum G { X } g :: () -> ()").is_ok());
+    }
+
+    #[test]
+    fn error_lines() {
+        assert_eq!(
+            error("fn f () -> () x { () }\n\nfn g () -> () x { () }\nfn f = g;"),
+            "test.sn:4: `f` is defined more than once in this module",
+        );
+        assert_eq!(error("fn f () -> () x { () }\nfn g () -> ()\n{ () }"), "test.sn:3: expected the input name of a `fn`");
+        assert_eq!(error("mod m {\n    sum A { X }\n    mul B X\n}"), "test.sn:3: expected `{`");
+    }
+
+    #[test]
+    fn powers() {
+        assert!(source("test.sn", "type Score = 5 ^ 2; type Big = 2 ^ 3 ^ 2;").is_ok());
+        assert_eq!(error("type Score = 5 ^;"), "test.sn:1: in a type alias: expected a number after `^`");
+        assert_eq!(error("type Huge = 2 ^ 64;"), "test.sn:1: in a type alias: number type does not fit in 64 bits");
+    }
+
+    #[test]
+    fn signatures() {
+        assert!(source("test.sn", "fnord :: () -> () fn fnord () -> () a { () } module :: () -> ()").is_ok());
+        assert_eq!(
+            error("f :: () -> ()\npub f :: () -> ()"),
+            "test.sn:2: a signature takes the visibility of its function and cannot have its own",
+        );
+        assert_eq!(error("f :: () -> ()\ng :: ()"), "test.sn:2: expected `->` after the input type of a signature");
+    }
+
+    /// Writes `files` into a fresh directory for the test `name`.
+    fn dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cyberlang-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, source) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn module_files() {
+        let dir = dir("module-files", &[
+            ("main.sn", "mod fruit; mod shared; fn start () -> () a { () }"),
+            ("fruit/mod.sn", "pub mod apple; mod inline { mod deep; }"),
+            ("fruit/apple.sn", "pub fn yes () -> () a { () }"),
+            ("fruit/inline/deep.sn", ""),
+            ("shared.sn", "mod inner;"),
+            ("shared/inner.sn", ""),
+        ]);
+        let (root, sources) = load(&dir.join("main.sn")).unwrap_or_else(|error| panic!("{error}"));
+        let mut paths: Vec<_> = sources.files.iter()
+            .map(|file| file.path.strip_prefix(&dir).unwrap().display().to_string())
+            .collect();
+        paths.sort();
+        assert_eq!(paths, ["fruit/apple.sn", "fruit/inline/deep.sn", "fruit/mod.sn", "main.sn", "shared.sn", "shared/inner.sn"]);
+        assert!(root.modules.values().all(|module| module.files.is_empty()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn module_file_errors() {
+        let dir = dir("module-file-errors", &[
+            ("missing.sn", "mod nothing;"),
+            ("ambiguous.sn", "mod both;"),
+            ("both.sn", ""),
+            ("both/mod.sn", ""),
+            ("cycle.sn", "mod cycle;"),
+            ("parse.sn", "mod parse_error;"),
+            ("parse_error.sn", "\nfn"),
+        ]);
+        let error = |file: &str| load(&dir.join(file)).err().expect("an error").to_string().replace(&format!("{}/", dir.display()), "");
+
+        assert_eq!(error("missing.sn"), "no file for module `nothing`, looked for nothing.sn and nothing/mod.sn");
+        assert_eq!(error("ambiguous.sn"), "module `both` has two files, both.sn and both/mod.sn");
+        assert_eq!(error("cycle.sn"), "cycle.sn is loaded as a module of itself");
+        assert_eq!(error("parse.sn"), "parse_error.sn:2: expected a name in snake case");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn resolve_errors_name_their_file() {
+        let dir = dir("resolve-errors", &[
+            ("main.sn", "mod shared; fn start () -> () a { shared::flag () }"),
+            ("shared.sn", "pub fn flag () -> () a { nope () }"),
+        ]);
+        let (root, sources) = load(&dir.join("main.sn")).unwrap_or_else(|error| panic!("{error}"));
+        let error = crate::referencer::resolve(&root).err().expect("an error");
+        assert_eq!(sources.files[error.file].path, dir.join("shared.sn"));
+        assert_eq!(error.error.to_string(), "unknown function `nope`");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
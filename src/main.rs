@@ -1,9 +1,75 @@
 
+use std::{fmt, path::{Path, PathBuf}};
+use referencer::Type;
+
 mod parser;
+mod loader;
+mod referencer;
+mod checker;
 mod compiler;
 mod vm;
-mod referencer;
+
+#[derive(Debug)]
+enum Error {
+    Load(loader::Error),
+    Resolve {
+        path: PathBuf,
+        error: referencer::Error,
+    },
+    /// A checker error, already written with the names of the program,
+    /// and the file of the function it was found in.
+    Check {
+        path: Option<PathBuf>,
+        error: String,
+    },
+    EntryMissing,
+    EntrySignature,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load(error) => write!(f, "{error}"),
+            Self::Resolve { path, error } => write!(f, "{}: {error}", path.display()),
+            Self::Check { path: Some(path), error } => write!(f, "{}: {error}", path.display()),
+            Self::Check { path: None, error } => write!(f, "{error}"),
+            Self::EntryMissing => write!(f, "no `start` function to run"),
+            Self::EntrySignature => write!(f, "`start` must take `()` and return `()`"),
+        }
+    }
+}
+
+fn run(path: &Path) -> Result<(), Error> {
+    let (root, sources) = loader::load(path).map_err(Error::Load)?;
+    let path_of = |file: usize| sources.files[file].path.clone();
+    let mut program = referencer::resolve(&root)
+        .map_err(|referencer::FileError { file, error }| Error::Resolve { path: path_of(file), error })?;
+    let table = checker::check(&mut program).map_err(|error| Error::Check {
+        path: error.function().map(|function| path_of(program.functions[function].file)),
+        error: program.named(&error).to_string(),
+    })?;
+    for warning in &table.warnings {
+        let path = path_of(program.functions[warning.function()].file);
+        eprintln!("warning: {}: {}", path.display(), program.named(warning));
+    }
+
+    let entry = program.entry.ok_or(Error::EntryMissing)?;
+    let unit = Type::Tuple(Box::default());
+    if program.functions[entry].input != unit || program.functions[entry].output != unit {
+        return Err(Error::EntrySignature);
+    }
+
+    let (scopes, layouts) = compiler::compile(&program, &table, &sources);
+    vm::Vm::new(layouts).run(entry, &[], 0, &scopes);
+
+    Ok(())
+}
 
 fn main() {
+    let path = std::env::args().nth(1).unwrap_or_else(|| "main.sn".into());
 
-}
\ No newline at end of file
+    if let Err(error) = run(Path::new(&path)) {
+        eprintln!("error: {error}");
+        std::process::exit(1);
+    }
+}
@@ -1,20 +1,20 @@
+use std::fmt;
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Instance {
     first: Part,
     others: Box<[Part]>
 }
 
 impl Instance {
-    pub fn from_chars_snake_case_prefix<'a>(mut value: &'a [char]) -> Option<(Self, &'a [char])> {
+    pub fn from_chars_snake_case_prefix(value: &[char]) -> Option<(Self, &[char])> {
         let (first, mut value) = Part::from_chars_lowercase_prefix(value)?;
         let mut others = Vec::new();
 
-        while let Some((&item, next_value)) = value.split_first() {
-            value = next_value;
-            if item != '_' { None? }
-
-            let (part, next_value) = Part::from_chars_lowercase_prefix(value)?;
+        while let Some(next_value) = value.strip_prefix(&['_']) {
+            let Some((part, next_value)) = Part::from_chars_lowercase_prefix(next_value) else {
+                break
+            };
             value = next_value;
             others.push(part);
         }
@@ -25,7 +25,7 @@ impl Instance {
         }, value))
     }
 
-    pub fn from_chars_pascal_case_prefix<'a>(mut value: &'a [char]) -> Option<(Self, &'a [char])> {
+    pub fn from_chars_pascal_case_prefix(value: &[char]) -> Option<(Self, &[char])> {
         let (first, mut value) = Part::from_chars_pascal_case_prefix(value)?;
         let mut others = Vec::new();
 
@@ -39,31 +39,54 @@ impl Instance {
             others: others.into()
         }, value))
     }
+}
+
+impl Instance {
+    pub fn to_pascal_case(&self) -> String {
+        std::iter::once(&self.first).chain(self.others.iter())
+            .map(Part::to_pascal_case)
+            .collect()
+    }
+}
+
+/// Names are ordered as they are written, so layouts, ids and messages list
+/// members alphabetically.
+impl Ord for Instance {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.to_string().cmp(&other.to_string())
+    }
+}
+
+impl PartialOrd for Instance {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-    pub fn from_chars_prefix<'a>(value: &'a [char]) -> Option<(Self, Case, &'a [char])> {
-        if let Some((instance, value)) = Self::from_chars_pascal_case_prefix(value) {
-            Some((instance, Case::Snake, value))
-        } else if let Some((instance, value)) = Self::from_chars_pascal_case_prefix(value) {
-            Some((instance, Case::Pascal, value))
-        } else {
-            None
+impl fmt::Display for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first)?;
+        for part in self.others.iter() {
+            write!(f, "_{part}")?;
         }
+        Ok(())
     }
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Part {
     first: Char,
     others: Box<[Char]>,
 }
 
 impl Part {
-    pub fn from_chars_lowercase_prefix<'a>(value: &'a [char]) -> Option<(Self, &'a [char])> {
+    pub fn from_chars_lowercase_prefix(value: &[char]) -> Option<(Self, &[char])> {
         let (&item, mut value) = value.split_first()?;
+        if !item.is_ascii_lowercase() { None? }
         let first = Char::from_lowercase_char(item)?;
         let mut others = Vec::new();
 
-        while let Some(item) = value.get(0) {
+        while let Some(item) = value.first() {
             if let Some(part) = Char::from_lowercase_char(*item) {
                 others.push(part);
                 value = &value[1..];
@@ -78,13 +101,13 @@ impl Part {
         }, value))
     }
 
-    pub fn from_chars_pascal_case_prefix<'a>(mut value: &'a [char]) -> Option<(Self, &'a [char])> {
+    pub fn from_chars_pascal_case_prefix(value: &[char]) -> Option<(Self, &[char])> {
         let (&item, mut value) = value.split_first()?;
         if !item.is_ascii_uppercase() { None? }
         let first = Char::from_char(item)?;
         let mut others = Vec::new();
 
-        while let Some(item) = value.get(0) {
+        while let Some(item) = value.first() {
             if let Some(part) = Char::from_lowercase_char(*item) {
                 others.push(part);
                 value = &value[1..];
@@ -100,22 +123,37 @@ impl Part {
     }
 }
 
+impl Part {
+    pub fn to_pascal_case(&self) -> String {
+        std::iter::once(self.first.to_char().to_ascii_uppercase())
+            .chain(self.others.iter().map(Char::to_char))
+            .collect()
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first.to_char())?;
+        for item in self.others.iter() {
+            write!(f, "{}", item.to_char())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
 pub enum Case {
     Snake,
     Pascal,
 }
 
 impl Case {
-    pub fn is_snake(&self) -> bool {
-        matches!(self, Self::Snake)
-    }
-
     pub fn is_pascal(&self) -> bool {
         matches!(self, Self::Pascal)
     }
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Char {
     Q, W, E, R, T, Y, U, I, O, P,
     A, S, D, F, G, H, J, K, L,
@@ -136,6 +174,22 @@ impl Char {
             'i' => Self::I,
             'o' => Self::O,
             'p' => Self::P,
+            'a' => Self::A,
+            's' => Self::S,
+            'd' => Self::D,
+            'f' => Self::F,
+            'g' => Self::G,
+            'h' => Self::H,
+            'j' => Self::J,
+            'k' => Self::K,
+            'l' => Self::L,
+            'z' => Self::Z,
+            'x' => Self::X,
+            'c' => Self::C,
+            'v' => Self::V,
+            'b' => Self::B,
+            'n' => Self::N,
+            'm' => Self::M,
             '1' => Self::One,
             '2' => Self::Two,
             '3' => Self::Three,
@@ -146,4 +200,38 @@ impl Char {
     pub fn from_char(value: char) -> Option<Self> {
         Self::from_lowercase_char(value.to_ascii_lowercase())
     }
+
+    pub fn to_char(&self) -> char {
+        match self {
+            Self::Q => 'q',
+            Self::W => 'w',
+            Self::E => 'e',
+            Self::R => 'r',
+            Self::T => 't',
+            Self::Y => 'y',
+            Self::U => 'u',
+            Self::I => 'i',
+            Self::O => 'o',
+            Self::P => 'p',
+            Self::A => 'a',
+            Self::S => 's',
+            Self::D => 'd',
+            Self::F => 'f',
+            Self::G => 'g',
+            Self::H => 'h',
+            Self::J => 'j',
+            Self::K => 'k',
+            Self::L => 'l',
+            Self::Z => 'z',
+            Self::X => 'x',
+            Self::C => 'c',
+            Self::V => 'v',
+            Self::B => 'b',
+            Self::N => 'n',
+            Self::M => 'm',
+            Self::One => '1',
+            Self::Two => '2',
+            Self::Three => '3',
+        }
+    }
 }
@@ -1,31 +1,63 @@
-use std::collections::HashMap;
-use ident::Instance as Ident;
-use path::Instance as Path;
+use std::{collections::HashMap, fmt};
+pub use ident::Instance as Ident;
+pub use path::{Instance as Path, Prefix, Root};
 
 mod ident;
-mod path;
 mod number;
+mod path;
 
 pub struct Module {
-    functions: HashMap<Ident, Function>,
-    // types: HashMap<Ident, Type>,
-    modules: HashMap<Ident, Module>
+    pub visibility: Visibility,
+    pub file: usize,
+    pub files: HashMap<Ident, Visibility>,
+    pub functions: HashMap<Ident, Function>,
+    pub signatures: HashMap<Ident, Signature>,
+    pub aliases: HashMap<Ident, Import>,
+    pub imports: HashMap<Ident, Import>,
+    pub globs: Vec<Glob>,
+    pub types: HashMap<Ident, TypeItem>,
+    pub modules: HashMap<Ident, Module>
 }
 
-pub enum EnvIdent {
-    Sub(Ident),
+/// Which modules may name an item: only its own module (and that module's
+/// submodules), also its parent, or every module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Visibility {
+    Private,
     Super,
+    Public,
+}
+
+pub struct Import {
+    pub visibility: Visibility,
+    pub path: Path,
 }
 
+/// A `use prefix::*;` item, bringing in every item of the module `prefix` names.
+pub struct Glob {
+    pub visibility: Visibility,
+    pub prefix: Prefix,
+}
+
+/// A `name :: Input -> Output` item, stating the type of the `fn` of the
+/// same name in the same module.
+pub struct Signature {
+    pub input: Type,
+    pub output: Type,
+}
 
 pub struct Function {
-    is_external: bool,
-    expression: Expression
+    pub visibility: Visibility,
+    pub input: Type,
+    pub output: Type,
+    pub input_name: Ident,
+    pub expression: Expression
 }
 
 pub enum Expression {
     Mul {
         path: Path,
+        base: Option<Box<Expression>>,
         fields: HashMap<Ident, Expression>
     },
     Sum {
@@ -39,42 +71,206 @@ pub enum Expression {
     },
     Match {
         on: Box<Expression>,
-        variants: HashMap<Ident, Expression>,
+        variants: Box<[(Pattern, Expression)]>,
+        source: MatchSource,
     },
     Member {
-        of: Ident,
+        of: Box<Expression>,
         name: Ident,
     },
     Variable {
         name: Ident
-    }
+    },
+    Block {
+        statements: Box<[Statement]>,
+        result: Box<Expression>,
+    },
+    Debug {
+        expression: Box<Expression>,
+        from_end: usize,
+    },
+    Tuple(Box<[Expression]>),
+    Record(HashMap<Ident, Expression>),
+    Variant {
+        tag: Ident,
+        body: Box<Expression>,
+    },
+    Lambda {
+        input: Ident,
+        body: Box<Expression>,
+    },
+    Number(u64),
 }
 
+/// What a `Match` was written as, which decides how its checks are reported.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MatchSource {
+    Match,
+    /// An `if`, whose last variant was filled in when there is no `else`.
+    If {
+        fallback: bool,
+    },
+    /// A destructuring `let`, matching the rest of its block, with the
+    /// `else` as a second variant when there is one. There is no type for
+    /// expressions that never finish, so the `else` diverges by leaving
+    /// the block: its value becomes the block's value, of the same type,
+    /// and the statements after the `let` do not run.
+    Let,
+}
+
+pub enum Statement {
+    Let {
+        name: Ident,
+        value: Expression,
+    },
+    Expression(Expression),
+}
+
+#[derive(Debug)]
 pub enum ExpressionFromCharsPrefixError {
     CloseBracketExpected,
+    CloseCurlyBraceExpected,
     UnexpectedPrefix,
     MulField(Box<MulFieldFromCharsPrefixError>),
     SumTagNameExpected,
     SumValue(Box<Self>),
-    UnexpectedTypeSuffix
+    VariantTagNameExpected,
+    MemberNameExpected,
+    UnexpectedTypeSuffix,
+    LambdaInputExpected,
+    LambdaOpenCurlyBraceExpected,
+    LetPattern(PatternFromCharsPrefixError),
+    LetEqualSignExpected,
+    LetSemicolonExpected,
+    IfPattern(PatternFromCharsPrefixError),
+    IfEqualSignExpected,
+    IfOpenCurlyBraceExpected,
+    MatchOpenCurlyBraceExpected,
+    MatchPattern(PatternFromCharsPrefixError),
+    MatchArrowExpected,
+    NumberTooLarge,
+}
+
+impl fmt::Display for ExpressionFromCharsPrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CloseBracketExpected => write!(f, "expected `)`"),
+            Self::CloseCurlyBraceExpected => write!(f, "expected `}}`"),
+            Self::UnexpectedPrefix => write!(f, "expected an expression"),
+            Self::MulField(error) => write!(f, "{error}"),
+            Self::SumTagNameExpected => write!(f, "expected a tag name after `:`"),
+            Self::SumValue(error) => write!(f, "{error}"),
+            Self::VariantTagNameExpected => write!(f, "expected a tag name after `` ` ``"),
+            Self::MemberNameExpected => write!(f, "expected a member name after `.`"),
+            Self::UnexpectedTypeSuffix => write!(f, "expected `:` or `{{` after a type name"),
+            Self::LambdaInputExpected => write!(f, "expected the input name of a `fn`"),
+            Self::LambdaOpenCurlyBraceExpected => write!(f, "expected `{{` after the input name of a `fn`"),
+            Self::LetPattern(error) => write!(f, "in the pattern of a `let`: {error}"),
+            Self::LetEqualSignExpected => write!(f, "expected `=` after the pattern of a `let`"),
+            Self::LetSemicolonExpected => write!(f, "expected `;` after a `let`"),
+            Self::IfPattern(error) => write!(f, "in the pattern of an `if`: {error}"),
+            Self::IfEqualSignExpected => write!(f, "expected `=` after the pattern of an `if`"),
+            Self::IfOpenCurlyBraceExpected => write!(f, "expected `{{` after the value of an `if`"),
+            Self::MatchOpenCurlyBraceExpected => write!(f, "expected `{{` after the value of a `match`"),
+            Self::MatchPattern(error) => write!(f, "in a pattern of a `match`: {error}"),
+            Self::MatchArrowExpected => write!(f, "expected `->` after a pattern of a `match`"),
+            Self::NumberTooLarge => write!(f, "number literal does not fit in 64 bits"),
+        }
+    }
 }
 
 impl Expression {
-    pub fn from_chars_prefix<'a>(value: &'a [char]) -> Result<(Self, &'a [char]), ExpressionFromCharsPrefixError> {
+    pub fn from_chars_prefix(value: &[char]) -> Result<(Self, &[char]), ExpressionFromCharsPrefixError> {
         type E = ExpressionFromCharsPrefixError;
 
-        if let Some((path, value)) = Path::from_char_prefix(value) {
+        if let Some(next_value) = value.strip_prefix(&['d', 'b', 'g', '!']) {
+            let from_end = value.len();
+            let next_value = strip_space(next_value);
+            let (expression, next_value) = Self::from_chars_prefix(next_value)?;
+
+            Ok((Self::Debug { expression: Box::new(expression), from_end }, next_value))
+
+        } else if let Some(value) = strip_keyword(value, &['f', 'n']) {
+            let value = strip_space(value);
+            let (input, value) = Ident::from_chars_snake_case_prefix(value).ok_or(E::LambdaInputExpected)?;
+            let value = strip_space(value);
+            let value = value.strip_prefix(&['{']).ok_or(E::LambdaOpenCurlyBraceExpected)?;
+            let (body, value) = Self::block_from_chars_prefix(value)?;
+
+            Ok((Self::Lambda { input, body: Box::new(body) }, value))
+
+        } else if let Some(value) = strip_keyword(value, &['i', 'f']) {
+            let value = strip_space(value);
+            let (pattern, value) = Pattern::try_from_chars_prefix(value).map_err(E::IfPattern)?;
+            let value = strip_space(value);
+            let value = value.strip_prefix(&['=']).ok_or(E::IfEqualSignExpected)?;
+            let value = strip_space(value);
+            let (on, value) = Self::from_chars_prefix(value)?;
+            let value = strip_space(value);
+            let value = value.strip_prefix(&['{']).ok_or(E::IfOpenCurlyBraceExpected)?;
+            let (then, value) = Self::block_from_chars_prefix(value)?;
+
+            let mut variants = vec![(pattern, then)];
+            let next_value = strip_space(value);
+            let (fallback, value) = if let Some(next_value) = strip_keyword(next_value, &['e', 'l', 's', 'e']) {
+                let next_value = strip_space(next_value);
+                let (otherwise, next_value) = Self::from_chars_prefix(next_value)?;
+                variants.push((Pattern::Wildcard, otherwise));
+                (false, next_value)
+            } else {
+                variants.push((Pattern::Wildcard, Self::Tuple(Box::default())));
+                (true, value)
+            };
+
+            Ok((Self::Match { on: Box::new(on), variants: variants.into(), source: MatchSource::If { fallback } }, value))
+
+        } else if let Some(value) = strip_keyword(value, &['m', 'a', 't', 'c', 'h']) {
+            let value = strip_space(value);
+            let (on, value) = Self::from_chars_prefix(value)?;
+            let value = strip_space(value);
+            let mut value = value.strip_prefix(&['{']).ok_or(E::MatchOpenCurlyBraceExpected)?;
+            let mut variants = Vec::new();
+
+            loop {
+                value = strip_space(value);
+                if let Some(next_value) = value.strip_prefix(&['}']) {
+                    value = next_value;
+                    break;
+                }
+
+                let (pattern, next_value) = Pattern::try_from_chars_prefix(value).map_err(E::MatchPattern)?;
+                let next_value = strip_space(next_value);
+                let (pattern, next_value) = match strip_keyword(next_value, &['i', 'f']) {
+                    Some(next_value) => Self::guard_from_chars_prefix(pattern, strip_space(next_value))?,
+                    None => (pattern, next_value),
+                };
+                let next_value = strip_space(next_value);
+                let next_value = next_value.strip_prefix(&['-', '>']).ok_or(E::MatchArrowExpected)?;
+                let next_value = strip_space(next_value);
+                let (result, next_value) = Self::from_chars_prefix(next_value)?;
+                let next_value = strip_space(next_value);
+                value = next_value.strip_prefix(&[',']).unwrap_or(next_value);
+
+                variants.push((pattern, result));
+            }
+
+            Ok((Self::Match { on: Box::new(on), variants: variants.into(), source: MatchSource::Match }, value))
+
+        } else if is_reserved(value) {
+            Err(E::UnexpectedPrefix)
+
+        } else if let Some((path, value)) = Path::from_char_prefix(value) {
             match path.case {
                 ident::Case::Snake => {
-                    if path.parts.is_empty() {
+                    if path.prefix.is_empty() {
                         let value = strip_space(value);
-                        match Self::from_chars_prefix(value) {
+                        match Self::input_from_chars_prefix(value) {
                             Ok((input, value)) => {
                                 Ok((Self::Call { path, input: Box::new(input) }, value))
                             },
 
                             Err(e) => if let E::UnexpectedPrefix = e {
-                                Ok((Self::Variable { name: path.name }, value))
+                                Self::members_from_chars_prefix(Self::Variable { name: path.name }, value)
                             } else {
                                 Err(e)
                             }
@@ -82,8 +278,8 @@ impl Expression {
 
                     } else {
                         let value = strip_space(value);
-                        
-                        match Self::from_chars_prefix(value) {
+
+                        match Self::input_from_chars_prefix(value) {
                             Ok((input, value)) => {
                                 Ok((Self::Call { path, input: Box::new(input) }, value))
                             },
@@ -104,6 +300,14 @@ impl Expression {
 
                     } else if let Some(mut value) = value.strip_prefix(&['{']) {
                         let mut fields = HashMap::new();
+                        let mut base = None;
+
+                        if let Some(next_value) = strip_space(value).strip_prefix(&['.', '.']) {
+                            let (expression, next_value) = Self::from_chars_prefix(strip_space(next_value))?;
+                            let next_value = strip_space(next_value);
+                            base = Some(Box::new(expression));
+                            value = next_value.strip_prefix(&[',']).unwrap_or(next_value);
+                        }
 
                         loop {
                             value = strip_space(value);
@@ -112,7 +316,8 @@ impl Expression {
                                 break;
 
                             } else {
-                                let (name, expression, next_value) = mul_field_from_chars_prefix(value).map_err(|e: MulFieldFromCharsPrefixError| E::MulField(Box::new(e)))?;
+                                let (name, expression, next_value) = mul_field_from_chars_prefix(value, Ident::from_chars_pascal_case_prefix)
+                                    .map_err(|e: MulFieldFromCharsPrefixError| E::MulField(Box::new(e)))?;
                                 value = next_value;
                                 fields.insert(name, expression);
                             }
@@ -120,6 +325,7 @@ impl Expression {
 
                         Ok((Self::Mul {
                             fields,
+                            base,
                             path
                         }, value))
                     } else {
@@ -128,79 +334,349 @@ impl Expression {
                 }
             }
         } else if let Some(value) = value.strip_prefix(&['(']) {
-            let (expression, value) = Expression::from_chars_prefix(value)?;
+            let (mut items, is_tuple, value) = tuple_from_chars_prefix(value, Self::from_chars_prefix, E::CloseBracketExpected)?;
+
+            let expression = if is_tuple {
+                Self::Tuple(items.into())
+            } else {
+                items.remove(0)
+            };
+            Self::members_from_chars_prefix(expression, value)
+
+        } else if let Some(value) = value.strip_prefix(&['`']) {
+            let (tag, value) = Ident::from_chars_snake_case_prefix(value).ok_or(E::VariantTagNameExpected)?;
             let value = strip_space(value);
-            let value = value.strip_prefix(&[')']).ok_or(E::CloseBracketExpected)?;
+            let (body, value) = Self::from_chars_prefix(value)?;
+
+            Ok((Self::Variant { tag, body: Box::new(body) }, value))
 
-            Ok((expression, value))
+        } else if let Some((number, value)) = number::Instance::from_chars_prefix(value) {
+            let number = number.value().ok_or(E::NumberTooLarge)?;
+            Ok((Self::Number(number), value))
+
+        } else if let Some(mut value) = value.strip_prefix(&['{']) {
+            if !is_record_field(value) {
+                return Self::block_from_chars_prefix(value);
+            }
+
+            let mut fields = HashMap::new();
+            loop {
+                value = strip_space(value);
+                if let Some(next_value) = value.strip_prefix(&['}']) {
+                    break Self::members_from_chars_prefix(Self::Record(fields), next_value);
+                }
+
+                let (name, expression, next_value) = mul_field_from_chars_prefix(value, Ident::from_chars_snake_case_prefix)
+                    .map_err(|e| E::MulField(Box::new(e)))?;
+                value = next_value;
+                fields.insert(name, expression);
+            }
 
         } else {
             Err(E::UnexpectedPrefix)?
         }
     }
+
+    fn block(statements: Vec<Statement>, result: Self) -> Self {
+        if statements.is_empty() {
+            result
+        } else {
+            Self::Block {
+                statements: statements.into(),
+                result: Box::new(result),
+            }
+        }
+    }
+
+    /// Applies any `.name` accesses following `of`, so `a.b.c` reads `c` of `b` of `a`.
+    fn members_from_chars_prefix(mut of: Self, mut value: &[char]) -> Result<(Self, &[char]), ExpressionFromCharsPrefixError> {
+        while let Some(next_value) = value.strip_prefix(&['.']) {
+            if next_value.starts_with(&['.']) {
+                break;
+            }
+
+            let (name, next_value) = Ident::from_chars_snake_case_prefix(next_value)
+                .or_else(|| Ident::from_chars_pascal_case_prefix(next_value))
+                .ok_or(ExpressionFromCharsPrefixError::MemberNameExpected)?;
+            of = Self::Member { of: Box::new(of), name };
+            value = next_value;
+        }
+
+        Ok((of, value))
+    }
+
+    /// Parses what follows the `if` of a guarded match variant: either
+    /// `pattern = expression` or an expression that must match `True _`.
+    fn guard_from_chars_prefix(pattern: Pattern, value: &[char]) -> Result<(Pattern, &[char]), ExpressionFromCharsPrefixError> {
+        let guard = Pattern::try_from_chars_prefix(value).ok().and_then(|(guard, next_value)| {
+            let next_value = strip_space(next_value);
+            match next_value.strip_prefix(&['=']) {
+                Some(next_value) if !next_value.starts_with(&['=']) => Some((guard, strip_space(next_value))),
+                _ => None,
+            }
+        });
+
+        let (guard, value) = match guard {
+            Some(guard) => guard,
+            None => {
+                let true_ = Ident::from_chars_pascal_case_prefix(&['T', 'r', 'u', 'e']).unwrap().0;
+                (Pattern::Tag(true_, Box::new(Pattern::Wildcard)), value)
+            },
+        };
+        let (on, value) = Self::from_chars_prefix(value)?;
+
+        Ok((Pattern::Guard { pattern: Box::new(pattern), guard: Box::new(guard), on }, value))
+    }
+
+    fn input_from_chars_prefix(value: &[char]) -> Result<(Self, &[char]), ExpressionFromCharsPrefixError> {
+        if value.starts_with(&['{']) && !is_record_field(&value[1..]) {
+            Err(ExpressionFromCharsPrefixError::UnexpectedPrefix)
+        } else {
+            Self::from_chars_prefix(value)
+        }
+    }
+
+    fn block_from_chars_prefix(mut value: &[char]) -> Result<(Self, &[char]), ExpressionFromCharsPrefixError> {
+        type E = ExpressionFromCharsPrefixError;
+        let mut statements = Vec::new();
+
+        loop {
+            value = strip_space(value);
+
+            if let Some(next_value) = value.strip_prefix(&['}']) {
+                let result = Self::Tuple(Box::default());
+                break Ok((Self::block(statements, result), next_value))
+
+            } else if let Some(next_value) = strip_keyword(value, &['l', 'e', 't']) {
+                let next_value = strip_space(next_value);
+                let (pattern, next_value) = Pattern::try_from_chars_prefix(next_value).map_err(E::LetPattern)?;
+                let next_value = strip_space(next_value);
+                let next_value = next_value.strip_prefix(&['=']).ok_or(E::LetEqualSignExpected)?;
+                let next_value = strip_space(next_value);
+                let (expression, next_value) = Self::from_chars_prefix(next_value)?;
+                let next_value = strip_space(next_value);
+                let (otherwise, next_value) = match strip_keyword(next_value, &['e', 'l', 's', 'e']) {
+                    Some(next_value) => {
+                        let (otherwise, next_value) = Self::from_chars_prefix(strip_space(next_value))?;
+                        (Some(otherwise), strip_space(next_value))
+                    },
+                    None => (None, next_value),
+                };
+                value = next_value.strip_prefix(&[';']).ok_or(E::LetSemicolonExpected)?;
+
+                match (pattern, otherwise) {
+                    (Pattern::Any(name), None) => statements.push(Statement::Let { name, value: expression }),
+
+                    // Any other pattern matches the rest of the block, which
+                    // the `else` stands in for when the pattern fails.
+                    (pattern, otherwise) => {
+                        let (rest, next_value) = Self::block_from_chars_prefix(value)?;
+                        let mut variants = vec![(pattern, rest)];
+                        variants.extend(otherwise.map(|otherwise| (Pattern::Wildcard, otherwise)));
+
+                        let result = Self::Match { on: Box::new(expression), variants: variants.into(), source: MatchSource::Let };
+                        break Ok((Self::block(statements, result), next_value))
+                    },
+                }
+
+            } else {
+                let (expression, next_value) = Self::from_chars_prefix(value)?;
+                let next_value = strip_space(next_value);
+
+                if let Some(next_value) = next_value.strip_prefix(&[';']) {
+                    value = next_value;
+                    statements.push(Statement::Expression(expression));
+
+                } else {
+                    let next_value = next_value.strip_prefix(&['}']).ok_or(E::CloseCurlyBraceExpected)?;
+                    break Ok((Self::block(statements, expression), next_value))
+                }
+            }
+        }
+    }
 }
 
+#[derive(Debug)]
 pub enum MulFieldFromCharsPrefixError {
     NameExpected,
     EqualSignExpected,
-    ExpressionExpected(ExpressionFromCharsPrefixError),
+    Expression(ExpressionFromCharsPrefixError),
+}
+
+impl fmt::Display for MulFieldFromCharsPrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NameExpected => write!(f, "expected a field name"),
+            Self::EqualSignExpected => write!(f, "expected `=` after a field name"),
+            Self::Expression(error) => write!(f, "{error}"),
+        }
+    }
 }
 
-fn mul_field_from_chars_prefix<'a>(value: &'a [char]) -> Result<(Ident, Expression, &'a [char]), MulFieldFromCharsPrefixError> {
+fn mul_field_from_chars_prefix<'a>(
+    value: &'a [char],
+    name_from_chars_prefix: impl Fn(&'a [char]) -> Option<(Ident, &'a [char])>,
+) -> Result<(Ident, Expression, &'a [char]), MulFieldFromCharsPrefixError> {
     type E = MulFieldFromCharsPrefixError;
-    let (name, value) = Ident::from_chars_pascal_case_prefix(value).ok_or(E::NameExpected)?;
+    let (name, value) = name_from_chars_prefix(value).ok_or(E::NameExpected)?;
     let value = strip_space(value);
     let value = value.strip_prefix(&['=']).ok_or(E::EqualSignExpected)?;
     let value = strip_space(value);
-    let (expression, value) = Expression::from_chars_prefix(value).map_err(E::ExpressionExpected)?;
+    let (expression, value) = Expression::from_chars_prefix(value).map_err(E::Expression)?;
 
+    let value = strip_space(value);
     let value = value.strip_prefix(&[',']).unwrap_or(value);
 
     Ok((name, expression, value))
 }
 
+/// Whether the text after a `{` starts a record field, telling `{ name = value }` apart from a block.
+fn is_record_field(value: &[char]) -> bool {
+    let Some((_, value)) = Ident::from_chars_snake_case_prefix(strip_space(value)) else {
+        return false
+    };
+    let value = strip_space(value);
+    value.starts_with(&['=']) && !value.starts_with(&['=', '='])
+}
+
 pub enum Pattern {
+    /// `~value`, or a number on its own: matches what equals `value`.
     Equals(Expression),
     Tag(Ident, Box<Self>),
     Fields(HashMap<Ident, Self>),
     Any(Ident),
+    Wildcard,
+    Tuple(Box<[Self]>),
+    /// Matches what any of the alternatives does; each binds the same names.
+    Or(Box<[Self]>),
+    /// `name @ pattern`: binds the whole value while matching it.
+    Bind(Ident, Box<Self>),
+    /// A match variant's pattern that only matches once the value of `on`
+    /// matches `guard` too. `if cond` is short for `if True _ = cond`.
+    Guard {
+        pattern: Box<Self>,
+        guard: Box<Self>,
+        on: Expression,
+    },
 }
 
+#[derive(Debug)]
 pub enum PatternFromCharsPrefixError {
     UnknownPrefix,
+    CloseBracketExpected,
+    FieldNameExpected,
+    FieldEqualSignExpected,
+    TagNameExpected,
+    Equals(Box<ExpressionFromCharsPrefixError>),
+}
+
+impl fmt::Display for PatternFromCharsPrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownPrefix => write!(f, "expected a pattern"),
+            Self::CloseBracketExpected => write!(f, "expected `)`"),
+            Self::FieldNameExpected => write!(f, "expected a field name"),
+            Self::FieldEqualSignExpected => write!(f, "expected `=` after a field name"),
+            Self::TagNameExpected => write!(f, "expected a tag name after `` ` ``"),
+            Self::Equals(error) => write!(f, "in the value of a `~` pattern: {error}"),
+        }
+    }
 }
 
 impl Pattern {
-    pub fn try_from_chars_prefix<'a>(value: &'a [char]) -> Result<(Self, &'a [char]), PatternFromCharsPrefixError> {
+    /// Parses a pattern, including alternatives separated by `|`.
+    pub fn try_from_chars_prefix(value: &[char]) -> Result<(Self, &[char]), PatternFromCharsPrefixError> {
+        let (first, mut value) = Self::primary_try_from_chars_prefix(value)?;
+        let mut alternatives = vec![first];
+
+        while let Some(next_value) = strip_space(value).strip_prefix(&['|']) {
+            let (alternative, next_value) = Self::primary_try_from_chars_prefix(strip_space(next_value))?;
+            alternatives.push(alternative);
+            value = next_value;
+        }
+
+        match alternatives.len() {
+            1 => Ok((alternatives.remove(0), value)),
+            _ => Ok((Self::Or(alternatives.into()), value)),
+        }
+    }
+
+    /// Parses a pattern that is not an alternative unless parenthesized, as
+    /// needed for the body of a tag.
+    pub fn primary_try_from_chars_prefix(value: &[char]) -> Result<(Self, &[char]), PatternFromCharsPrefixError> {
         type E = PatternFromCharsPrefixError;
 
         if let Some((name, value)) = Ident::from_chars_pascal_case_prefix(value) {
-            match Self::try_from_chars_prefix(value) {
-                Ok((body, value)) => {
-                    Ok((Self::Tag(name, Box::new(body)), value))
+            let value = strip_space(value);
+            let (body, value) = Self::primary_try_from_chars_prefix(value)?;
+            Ok((Self::Tag(name, Box::new(body)), value))
+
+        } else if let Some(value) = value.strip_prefix(&['`']) {
+            let (tag, value) = Ident::from_chars_snake_case_prefix(value).ok_or(E::TagNameExpected)?;
+            let value = strip_space(value);
+            let (body, value) = Self::primary_try_from_chars_prefix(value)?;
+            Ok((Self::Tag(tag, Box::new(body)), value))
+
+        } else if let Some((name, value)) = Ident::from_chars_snake_case_prefix(value) {
+            match strip_space(value).strip_prefix(&['@']) {
+                Some(next_value) => {
+                    let (pattern, value) = Self::primary_try_from_chars_prefix(strip_space(next_value))?;
+                    Ok((Self::Bind(name, Box::new(pattern)), value))
                 },
-                Err(err) => if let PatternFromCharsPrefixError::UnknownPrefix = err {
-                    Ok((Self::Any(name), value))
-                } else {
-                    Err(err)
+                None => Ok((Self::Any(name), value)),
+            }
+
+        } else if let Some(value) = value.strip_prefix(&['_']) {
+            Ok((Self::Wildcard, value))
+
+        } else if let Some(value) = value.strip_prefix(&['~']) {
+            let (expression, value) = Expression::from_chars_prefix(strip_space(value)).map_err(|e| E::Equals(Box::new(e)))?;
+            Ok((Self::Equals(expression), value))
+
+        } else if let Some((number, value)) = number::Instance::from_chars_prefix(value) {
+            let number = number.value().ok_or(E::Equals(Box::new(ExpressionFromCharsPrefixError::NumberTooLarge)))?;
+            Ok((Self::Equals(Expression::Number(number)), value))
+
+        } else if let Some(value) = value.strip_prefix(&['(']) {
+            let (mut items, is_tuple, value) = tuple_from_chars_prefix(value, Self::try_from_chars_prefix, E::CloseBracketExpected)?;
+
+            if is_tuple {
+                Ok((Self::Tuple(items.into()), value))
+            } else {
+                Ok((items.remove(0), value))
+            }
+
+        } else if let Some(mut value) = value.strip_prefix(&['{']) {
+            let mut fields = HashMap::new();
+
+            loop {
+                value = strip_space(value);
+                if let Some(next_value) = value.strip_prefix(&['}']) {
+                    value = next_value;
+                    break;
                 }
+
+                let (name, next_value) = Ident::from_chars_pascal_case_prefix(value)
+                    .or_else(|| Ident::from_chars_snake_case_prefix(value))
+                    .ok_or(E::FieldNameExpected)?;
+                let next_value = strip_space(next_value);
+                let next_value = next_value.strip_prefix(&['=']).ok_or(E::FieldEqualSignExpected)?;
+                let next_value = strip_space(next_value);
+                let (pattern, next_value) = Self::try_from_chars_prefix(next_value)?;
+                let next_value = strip_space(next_value);
+                value = next_value.strip_prefix(&[',']).unwrap_or(next_value);
+
+                fields.insert(name, pattern);
             }
 
-        } else if let Some(value) = value.strip_prefix(&['{']) {
-            
+            Ok((Self::Fields(fields), value))
+
         } else {
             Err(E::UnknownPrefix)
         }
     }
 }
 
-pub enum Init {
-    Struct {
-        path: Path,
-        fields: HashMap<Ident, Expression>,
-    },
-}
-
 pub enum PathPart {
     Super,
     Module(Ident)
@@ -208,31 +684,267 @@ pub enum PathPart {
 
 type PathBase = Vec<PathPart>;
 
-fn normalize_path_base(value: PathBase) -> Vec<Ident> {
-    let types: HashMap<usize, Type> = HashMap::new();
-    let modules: HashMap<usize, Module> = HashMap::new();
-    let functions: HashMap<usize, Function> = HashMap::new();
-
+/// Cancels each `super` against the module before it, returning how many are
+/// left over to climb from where the path starts along with the remaining modules.
+fn normalize_path_base(value: PathBase) -> (usize, Vec<Ident>) {
+    let mut supers = 0;
     let mut parts = Vec::new();
     for part in value {
         match part {
-            PathPart::Super => { parts.pop(); },
+            PathPart::Super => if parts.pop().is_none() { supers += 1 },
             PathPart::Module(name) => parts.push(name)
         }
     }
-    parts
+    (supers, parts)
 }
 
 pub enum Type {
-    Path(Path),
+    Path(Path, Box<[Type]>),
+    /// The numbers below this one.
+    Number(u64),
+    Tuple(Box<[Type]>),
+    Hole,
+    Record(HashMap<Ident, Type>),
+    Variant(HashMap<Ident, Type>),
+    Function(Box<Type>, Box<Type>),
+}
 
+#[derive(Debug)]
+pub enum TypeFromCharsPrefixError {
+    UnexpectedPrefix,
+    PascalCaseExpected,
+    CloseBracketExpected,
+    CloseAngleBracketExpected,
+    FieldNameExpected,
+    FieldColonExpected,
+    TagNameExpected,
+    NumberTooLarge,
+    ExponentExpected,
+}
+
+impl fmt::Display for TypeFromCharsPrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedPrefix => write!(f, "expected a type"),
+            Self::PascalCaseExpected => write!(f, "expected a type name in pascal case"),
+            Self::CloseBracketExpected => write!(f, "expected `)`"),
+            Self::CloseAngleBracketExpected => write!(f, "expected `>`"),
+            Self::FieldNameExpected => write!(f, "expected a field name"),
+            Self::FieldColonExpected => write!(f, "expected `:` after a field name"),
+            Self::TagNameExpected => write!(f, "expected a tag name after `` ` ``"),
+            Self::NumberTooLarge => write!(f, "number type does not fit in 64 bits"),
+            Self::ExponentExpected => write!(f, "expected a number after `^`"),
+        }
+    }
+}
+
+impl Type {
+    /// Parses a type, including a function type `In -> Out`, whose output
+    /// extends as far right as it can.
+    pub fn from_chars_prefix(value: &[char]) -> Result<(Self, &[char]), TypeFromCharsPrefixError> {
+        let (input, value) = Self::primary_from_chars_prefix(value)?;
+
+        match strip_space(value).strip_prefix(&['-', '>']) {
+            Some(next_value) => {
+                let (output, value) = Self::from_chars_prefix(strip_space(next_value))?;
+                Ok((Self::Function(Box::new(input), Box::new(output)), value))
+            },
+            None => Ok((input, value)),
+        }
+    }
+
+    /// Parses a number type `base` or `base ^ exponent`, where the exponent
+    /// is itself a number type, so `2 ^ 3 ^ 2` is `2 ^ 9`.
+    fn power_from_chars_prefix(value: &[char]) -> Result<(u64, &[char]), TypeFromCharsPrefixError> {
+        type E = TypeFromCharsPrefixError;
+
+        let (base, value) = number::Instance::from_chars_prefix(value).ok_or(E::UnexpectedPrefix)?;
+        let base = base.value().ok_or(E::NumberTooLarge)?;
+
+        match strip_space(value).strip_prefix(&['^']) {
+            Some(value) => {
+                let value = strip_space(value);
+                if number::Instance::from_chars_prefix(value).is_none() {
+                    return Err(E::ExponentExpected);
+                }
+
+                let (exponent, value) = Self::power_from_chars_prefix(value)?;
+                let exponent = u32::try_from(exponent).map_err(|_| E::NumberTooLarge)?;
+                Ok((base.checked_pow(exponent).ok_or(E::NumberTooLarge)?, value))
+            },
+            None => Ok((base, value)),
+        }
+    }
+
+    /// Parses a type that is not a function type unless parenthesized, as
+    /// needed where a `->` follows, like the input of a `fn`.
+    pub fn primary_from_chars_prefix(value: &[char]) -> Result<(Self, &[char]), TypeFromCharsPrefixError> {
+        type E = TypeFromCharsPrefixError;
+
+        if let Some((path, value)) = Path::from_char_prefix(value) {
+            if path.case.is_pascal() {
+                match value.strip_prefix(&['<']) {
+                    Some(value) => {
+                        let (arguments, value) = angle_list_from_chars_prefix(value, Self::from_chars_prefix, E::CloseAngleBracketExpected)?;
+                        Ok((Self::Path(path, arguments.into()), value))
+                    },
+                    None => Ok((Self::Path(path, Box::default()), value)),
+                }
+            } else {
+                Err(E::PascalCaseExpected)
+            }
+
+        } else if let Some(value) = value.strip_prefix(&['(']) {
+            let (mut items, is_tuple, value) = tuple_from_chars_prefix(value, Self::from_chars_prefix, E::CloseBracketExpected)?;
+
+            if is_tuple {
+                Ok((Self::Tuple(items.into()), value))
+            } else {
+                Ok((items.remove(0), value))
+            }
+
+        } else if let Some(value) = value.strip_prefix(&['?']) {
+            Ok((Self::Hole, value))
+
+        } else if number::Instance::from_chars_prefix(value).is_some() {
+            let (number, value) = Self::power_from_chars_prefix(value)?;
+            Ok((Self::Number(number), value))
+
+        } else if let Some(mut value) = value.strip_prefix(&['{']) {
+            let mut fields = HashMap::new();
+
+            loop {
+                value = strip_space(value);
+                if let Some(next_value) = value.strip_prefix(&['}']) {
+                    break Ok((Self::Record(fields), next_value));
+                }
+
+                let (name, next_value) = Ident::from_chars_snake_case_prefix(value).ok_or(E::FieldNameExpected)?;
+                let next_value = strip_space(next_value);
+                let next_value = next_value.strip_prefix(&[':']).ok_or(E::FieldColonExpected)?;
+                let next_value = strip_space(next_value);
+                let (type_, next_value) = Self::from_chars_prefix(next_value)?;
+                let next_value = strip_space(next_value);
+                value = next_value.strip_prefix(&[',']).unwrap_or(next_value);
+
+                fields.insert(name, type_);
+            }
+
+        } else if value.starts_with(&['`']) {
+            let mut variants = HashMap::new();
+            let mut value = value;
+
+            loop {
+                let next_value = value.strip_prefix(&['`']).ok_or(E::TagNameExpected)?;
+                let (tag, next_value) = Ident::from_chars_snake_case_prefix(next_value).ok_or(E::TagNameExpected)?;
+                let next_value = strip_space(next_value);
+                let (type_, next_value) = Self::from_chars_prefix(next_value)?;
+                variants.insert(tag, type_);
+
+                value = next_value;
+                let next_value = strip_space(next_value);
+                match next_value.strip_prefix(&['|']) {
+                    Some(next_value) => value = strip_space(next_value),
+                    None => break,
+                }
+            }
+
+            Ok((Self::Variant(variants), value))
+
+        } else {
+            Err(E::UnexpectedPrefix)
+        }
+    }
 }
 
 pub enum TypeItem {
-    Struct(Struct)
+    Struct(Struct),
+    Enum(Enum),
+    Alias(TypeAlias),
 }
 
-fn strip_space<'a>(value: &'a [char]) -> &'a [char] {
+impl TypeItem {
+    pub fn visibility(&self) -> Visibility {
+        match self {
+            Self::Struct(value) => value.visibility,
+            Self::Enum(value) => value.visibility,
+            Self::Alias(value) => value.visibility,
+        }
+    }
+
+    pub fn parameters(&self) -> &[Ident] {
+        match self {
+            Self::Struct(value) => &value.parameters,
+            Self::Enum(value) => &value.parameters,
+            Self::Alias(value) => &value.parameters,
+        }
+    }
+}
+
+fn tuple_from_chars_prefix<'a, T, E>(
+    mut value: &'a [char],
+    item_from_chars_prefix: impl Fn(&'a [char]) -> Result<(T, &'a [char]), E>,
+    close_bracket_expected: E,
+) -> Result<(Vec<T>, bool, &'a [char]), E> {
+    let mut items = Vec::new();
+    let mut has_comma = false;
+
+    loop {
+        value = strip_space(value);
+        if value.starts_with(&[')']) {
+            break;
+        }
+
+        let (item, next_value) = item_from_chars_prefix(value)?;
+        items.push(item);
+        value = strip_space(next_value);
+
+        if let Some(next_value) = value.strip_prefix(&[',']) {
+            value = next_value;
+            has_comma = true;
+        } else {
+            break;
+        }
+    }
+
+    let value = value.strip_prefix(&[')']).ok_or(close_bracket_expected)?;
+    let is_tuple = has_comma || items.len() != 1;
+    Ok((items, is_tuple, value))
+}
+
+/// Parses comma separated items up to a closing `>`, the opening `<` already stripped.
+fn angle_list_from_chars_prefix<'a, T, E>(
+    mut value: &'a [char],
+    item_from_chars_prefix: impl Fn(&'a [char]) -> Result<(T, &'a [char]), E>,
+    close_bracket_expected: E,
+) -> Result<(Vec<T>, &'a [char]), E> {
+    let mut items = Vec::new();
+
+    loop {
+        value = strip_space(value);
+        if value.starts_with(&['>']) {
+            break;
+        }
+
+        let (item, next_value) = item_from_chars_prefix(value)?;
+        items.push(item);
+        value = strip_space(next_value);
+
+        match value.strip_prefix(&[',']) {
+            Some(next_value) => value = next_value,
+            None => break,
+        }
+    }
+
+    let value = value.strip_prefix(&['>']).ok_or(close_bracket_expected)?;
+    Ok((items, value))
+}
+
+pub fn line_of(source: &[char], from_end: usize) -> usize {
+    source[..source.len() - from_end].iter().filter(|&&item| item == '\n').count() + 1
+}
+
+fn strip_space(value: &[char]) -> &[char] {
     let mut idx = 0;
     for &item in value.iter() {
         if " \n".contains(item) {
@@ -245,81 +957,414 @@ fn strip_space<'a>(value: &'a [char]) -> &'a [char] {
     &value[idx..]
 }
 
+fn strip_keyword<'a>(value: &'a [char], keyword: &[char]) -> Option<&'a [char]> {
+    let value = value.strip_prefix(keyword)?;
+    match value.first() {
+        Some(item) if item.is_ascii_alphanumeric() || *item == '_' => None,
+        _ => Some(value)
+    }
+}
+
+/// Keywords that never start an expression, so a name stops before them
+/// instead of taking them as its input.
+const RESERVED: [&[char]; 9] = [
+    &['e', 'l', 's', 'e'],
+    &['l', 'e', 't'],
+    &['a', 's'],
+    &['p', 'u', 'b'],
+    &['u', 's', 'e'],
+    &['m', 'o', 'd'],
+    &['m', 'u', 'l'],
+    &['s', 'u', 'm'],
+    &['t', 'y', 'p', 'e'],
+];
+
+fn is_reserved(value: &[char]) -> bool {
+    RESERVED.iter().any(|keyword| strip_keyword(value, keyword).is_some())
+}
+
 pub enum Item {
-    Function(Function),
-    Module(Module),
+    Function(Ident, Function),
+    Signature(Ident, Signature),
+    Alias(Ident, Import),
+    Import(Ident, Import),
+    Glob(Glob),
+    Module(Ident, Module),
+    File(Ident, Visibility),
+    Type(Ident, TypeItem),
 }
 
+#[derive(Debug)]
 pub enum ItemFromCharsPrefixError {
     UnknownPrefix,
     FnNameExpected,
+    FnAliasPathExpected,
+    SemicolonExpected,
+    UsePathExpected,
+    UseAliasExpected,
+    VisibilityScopeExpected,
+    FnInput(TypeFromCharsPrefixError),
+    FnArrowExpected,
+    FnOutput(TypeFromCharsPrefixError),
+    FnInputNameExpected,
+    SignatureInput(TypeFromCharsPrefixError),
+    SignatureArrowExpected,
+    /// A signature takes the visibility of its function, so it has none of its own.
+    SignatureVisibility,
+    SignatureOutput(TypeFromCharsPrefixError),
+    TypeNameExpected,
+    TypeParameterExpected,
+    CloseAngleBracketExpected,
+    TypeFieldNameExpected,
+    TypeFieldColonExpected,
+    TypeField(TypeFromCharsPrefixError),
+    TypeAliasEqualSignExpected,
+    TypeAlias(TypeFromCharsPrefixError),
     OpenCurlyBraceExpected,
     CloseCurlyBraceExpected,
-    FnExpression(ExpressionFromCharsPrefixError)
+    FnExpression(ExpressionFromCharsPrefixError),
+    /// A second item of the same kind with the same name in one module.
+    Duplicate(Path),
+}
+
+impl ItemFromCharsPrefixError {
+    fn at(self, value: &[char]) -> ItemError {
+        ItemError { error: self, from_end: value.len() }
+    }
+}
+
+/// An error in an item, with the length of the source left where it was
+/// found, which `line_of` turns into a line.
+#[derive(Debug)]
+pub struct ItemError {
+    pub error: ItemFromCharsPrefixError,
+    pub from_end: usize,
+}
+
+impl fmt::Display for ItemFromCharsPrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownPrefix => write!(f, "expected an item"),
+            Self::FnNameExpected => write!(f, "expected a name in snake case"),
+            Self::FnAliasPathExpected => write!(f, "expected the path of a function after `=`"),
+            Self::SemicolonExpected => write!(f, "expected `;`"),
+            Self::UsePathExpected => write!(f, "expected a path after `use`"),
+            Self::UseAliasExpected => write!(f, "expected a name after `as`"),
+            Self::VisibilityScopeExpected => write!(f, "expected `super` inside `pub(..)`"),
+            Self::FnInput(error) => write!(f, "in the input type of a `fn`: {error}"),
+            Self::FnArrowExpected => write!(f, "expected `->` after the input type of a `fn`"),
+            Self::FnOutput(error) => write!(f, "in the output type of a `fn`: {error}"),
+            Self::FnInputNameExpected => write!(f, "expected the input name of a `fn`"),
+            Self::SignatureInput(error) => write!(f, "in the input type of a signature: {error}"),
+            Self::SignatureArrowExpected => write!(f, "expected `->` after the input type of a signature"),
+            Self::SignatureVisibility => write!(f, "a signature takes the visibility of its function and cannot have its own"),
+            Self::SignatureOutput(error) => write!(f, "in the output type of a signature: {error}"),
+            Self::TypeNameExpected => write!(f, "expected a type name in pascal case"),
+            Self::TypeParameterExpected => write!(f, "expected a type parameter name"),
+            Self::CloseAngleBracketExpected => write!(f, "expected `>`"),
+            Self::TypeFieldNameExpected => write!(f, "expected a member name"),
+            Self::TypeFieldColonExpected => write!(f, "expected `:` after a member name"),
+            Self::TypeField(error) => write!(f, "in the type of a member: {error}"),
+            Self::TypeAliasEqualSignExpected => write!(f, "expected `=` after the name of a type alias"),
+            Self::TypeAlias(error) => write!(f, "in a type alias: {error}"),
+            Self::OpenCurlyBraceExpected => write!(f, "expected `{{`"),
+            Self::CloseCurlyBraceExpected => write!(f, "expected `}}`"),
+            Self::FnExpression(error) => write!(f, "{error}"),
+            Self::Duplicate(path) => write!(f, "`{path}` is defined more than once in this module"),
+        }
+    }
 }
 
 impl Item {
-    pub fn try_from_chars_prefix<'a>(value: &'a [char]) -> Result<(Ident, Self, &'a [char]), ItemFromCharsPrefixError> {
+    pub fn try_from_chars_prefix(value: &[char]) -> Result<(Self, &[char]), ItemError> {
         type E = ItemFromCharsPrefixError;
 
-        if let Some(value) = value.strip_prefix(&['f', 'n']) {
-            let value = strip_space(value);
-            let (name, value) = ident::Instance::from_chars_snake_case_prefix(value).ok_or(E::FnNameExpected)?;
+        let (visibility, value) = match strip_keyword(value, &['p', 'u', 'b']) {
+            Some(value) => match value.strip_prefix(&['(']) {
+                Some(value) => {
+                    let value = strip_space(value);
+                    let value = strip_keyword(value, &['s', 'u', 'p', 'e', 'r']).ok_or_else(|| E::VisibilityScopeExpected.at(value))?;
+                    let value = strip_space(value);
+                    let value = value.strip_prefix(&[')']).ok_or_else(|| E::VisibilityScopeExpected.at(value))?;
+                    (Visibility::Super, strip_space(value))
+                },
+                None => (Visibility::Public, strip_space(value)),
+            },
+            None => (Visibility::Private, value),
+        };
+
+        if let Some(value) = strip_keyword(value, &['f', 'n']) {
             let value = strip_space(value);
-            let value = value.strip_prefix(&['{']).ok_or(E::OpenCurlyBraceExpected)?;
+            let (name, value) = ident::Instance::from_chars_snake_case_prefix(value).ok_or_else(|| E::FnNameExpected.at(value))?;
             let value = strip_space(value);
 
-            let (expression, value) = Expression::from_chars_prefix(value).map_err(E::FnExpression)?;
+            if let Some(value) = value.strip_prefix(&['=']) {
+                let value = strip_space(value);
+                let (path, value) = Path::from_char_prefix(value).ok_or_else(|| E::FnAliasPathExpected.at(value))?;
+                let value = strip_space(value);
+                let value = value.strip_prefix(&[';']).ok_or_else(|| E::SemicolonExpected.at(value))?;
+                return Ok((Self::Alias(name, Import { visibility, path }), value));
+            }
+
+            let (input, value) = Type::primary_from_chars_prefix(value).map_err(|error| E::FnInput(error).at(value))?;
+            let value = strip_space(value);
+            let value = value.strip_prefix(&['-', '>']).ok_or_else(|| E::FnArrowExpected.at(value))?;
             let value = strip_space(value);
-            let value = value.strip_prefix(&['}']).ok_or(E::CloseCurlyBraceExpected)?;
+            let (output, value) = Type::from_chars_prefix(value).map_err(|error| E::FnOutput(error).at(value))?;
+            let value = strip_space(value);
+            let (input_name, value) = ident::Instance::from_chars_snake_case_prefix(value).ok_or_else(|| E::FnInputNameExpected.at(value))?;
+            let value = strip_space(value);
+            let value = value.strip_prefix(&['{']).ok_or_else(|| E::OpenCurlyBraceExpected.at(value))?;
+
+            let (expression, value) = Expression::block_from_chars_prefix(value).map_err(|error| E::FnExpression(error).at(value))?;
 
-            Ok((name, Self::Function(Function {
+            Ok((Self::Function(name, Function {
                 expression,
-                is_external: false,
+                input,
+                output,
+                input_name,
+                visibility,
             }), value))
 
-        } else if let Some(value) = value.strip_prefix(&['m', 'o', 'd']) {
+        } else if let Some(value) = strip_keyword(value, &['u', 's', 'e']) {
             let value = strip_space(value);
-            let (name, value) = ident::Instance::from_chars_snake_case_prefix(value).ok_or(E::FnNameExpected)?;
+            let (prefix, rest) = Prefix::from_char_prefix(value);
+            if let Some(value) = rest.strip_prefix(&['*']) {
+                let value = strip_space(value);
+                let value = value.strip_prefix(&[';']).ok_or_else(|| E::SemicolonExpected.at(value))?;
+                return Ok((Self::Glob(Glob { visibility, prefix }), value));
+            }
+
+            let (path, value) = Path::from_char_prefix(value).ok_or_else(|| E::UsePathExpected.at(value))?;
+
             let value = strip_space(value);
-            let mut value = value.strip_prefix(&['{']).ok_or(E::OpenCurlyBraceExpected)?;
+            let (name, value) = match strip_keyword(value, &['a', 's']) {
+                Some(value) => {
+                    let value = strip_space(value);
+                    let (name, value) = Ident::from_chars_snake_case_prefix(value)
+                        .or_else(|| Ident::from_chars_pascal_case_prefix(value))
+                        .ok_or_else(|| E::UseAliasExpected.at(value))?;
+                    (name, strip_space(value))
+                },
+                None => (path.name.clone(), value),
+            };
+            let value = value.strip_prefix(&[';']).ok_or_else(|| E::SemicolonExpected.at(value))?;
 
-            let mut functions = HashMap::new();
-            let mut modules = HashMap::new();
+            Ok((Self::Import(name, Import { visibility, path }), value))
 
-            loop {
-                value = strip_space(value);
+        } else if let Some(value) = strip_keyword(value, &['m', 'o', 'd']) {
+            let value = strip_space(value);
+            let (name, value) = ident::Instance::from_chars_snake_case_prefix(value).ok_or_else(|| E::FnNameExpected.at(value))?;
+            let value = strip_space(value);
 
-                if let Some(next_value) = value.strip_prefix(&['}']) {
-                    value = next_value;
-                    break;
+            if let Some(value) = value.strip_prefix(&[';']) {
+                return Ok((Self::File(name, visibility), value));
+            }
 
-                } else {
-                    let (name, item, next_value) = Self::try_from_chars_prefix(value)?;
-                    value = next_value;
+            let value = value.strip_prefix(&['{']).ok_or_else(|| E::OpenCurlyBraceExpected.at(value))?;
+            let (module, value) = Module::items_from_chars_prefix(value, visibility)?;
+            let value = value.strip_prefix(&['}']).ok_or_else(|| E::CloseCurlyBraceExpected.at(value))?;
 
-                    match item {
-                        Item::Function(function) => {
-                            functions.insert(name, function);
-                        }
-                        Item::Module(module) => {
-                            modules.insert(name, module);
-                        }
-                    }
-                }
+            Ok((Self::Module(name, module), value))
+
+        } else if let Some(value) = strip_keyword(value, &['m', 'u', 'l']) {
+            let value = strip_space(value);
+            let (name, value) = Ident::from_chars_pascal_case_prefix(value).ok_or_else(|| E::TypeNameExpected.at(value))?;
+            let (parameters, value) = type_parameters_from_chars_prefix(value)?;
+            let value = strip_space(value);
+            let (fields, value) = type_fields_from_chars_prefix(value, false)?;
+
+            Ok((Self::Type(name, TypeItem::Struct(Struct { visibility, parameters, fields })), value))
+
+        } else if let Some(value) = strip_keyword(value, &['s', 'u', 'm']) {
+            let value = strip_space(value);
+            let (name, value) = Ident::from_chars_pascal_case_prefix(value).ok_or_else(|| E::TypeNameExpected.at(value))?;
+            let (parameters, value) = type_parameters_from_chars_prefix(value)?;
+            let value = strip_space(value);
+            let (variants, value) = type_fields_from_chars_prefix(value, true)?;
+
+            Ok((Self::Type(name, TypeItem::Enum(Enum { visibility, parameters, variants })), value))
+
+        } else if let Some(value) = strip_keyword(value, &['t', 'y', 'p', 'e']) {
+            let value = strip_space(value);
+            let (name, value) = Ident::from_chars_pascal_case_prefix(value).ok_or_else(|| E::TypeNameExpected.at(value))?;
+            let (parameters, value) = type_parameters_from_chars_prefix(value)?;
+            let value = strip_space(value);
+            let value = value.strip_prefix(&['=']).ok_or_else(|| E::TypeAliasEqualSignExpected.at(value))?;
+            let value = strip_space(value);
+            let (type_, value) = Type::from_chars_prefix(value).map_err(|error| E::TypeAlias(error).at(value))?;
+            let value = strip_space(value);
+            let value = value.strip_prefix(&[';']).ok_or_else(|| E::SemicolonExpected.at(value))?;
+
+            Ok((Self::Type(name, TypeItem::Alias(TypeAlias { visibility, parameters, type_ })), value))
+
+        } else if let Some((name, value)) = Ident::from_chars_snake_case_prefix(value)
+            .and_then(|(name, value)| Some((name, strip_space(value).strip_prefix(&[':', ':'])?)))
+        {
+            if visibility != Visibility::Private {
+                return Err(E::SignatureVisibility.at(value));
             }
 
-            Ok((name, Self::Module(Module {
-                functions,
-                modules,
-            }), value))
+            let value = strip_space(value);
+            let (input, value) = Type::primary_from_chars_prefix(value).map_err(|error| E::SignatureInput(error).at(value))?;
+            let value = strip_space(value);
+            let value = value.strip_prefix(&['-', '>']).ok_or_else(|| E::SignatureArrowExpected.at(value))?;
+            let value = strip_space(value);
+            let (output, value) = Type::from_chars_prefix(value).map_err(|error| E::SignatureOutput(error).at(value))?;
+            let value = strip_space(value);
+            let value = value.strip_prefix(&[';']).unwrap_or(value);
+
+            Ok((Self::Signature(name, Signature { input, output }), value))
 
         } else {
-            Err(E::UnknownPrefix)
+            Err(E::UnknownPrefix.at(value))
+        }
+    }
+}
+
+impl Module {
+    /// Parses a whole file as the body of a module.
+    pub fn from_chars(value: &[char]) -> Result<Self, ItemError> {
+        let (module, value) = Self::items_from_chars_prefix(value, Visibility::Private)?;
+        match value.is_empty() {
+            true => Ok(module),
+            false => Err(ItemFromCharsPrefixError::UnknownPrefix.at(value)),
+        }
+    }
+
+    fn items_from_chars_prefix(mut value: &[char], visibility: Visibility) -> Result<(Self, &[char]), ItemError> {
+        let mut module = Self {
+            visibility,
+            file: 0,
+            files: HashMap::new(),
+            functions: HashMap::new(),
+            signatures: HashMap::new(),
+            aliases: HashMap::new(),
+            imports: HashMap::new(),
+            globs: Vec::new(),
+            types: HashMap::new(),
+            modules: HashMap::new(),
+        };
+
+        loop {
+            value = strip_space(value);
+            if value.is_empty() || value.starts_with(&['}']) {
+                break Ok((module, value));
+            }
+
+            let (item, next_value) = Item::try_from_chars_prefix(value)?;
+            let start = value;
+            value = next_value;
+
+            // Functions and their aliases share one namespace, as do modules
+            // and the files that hold them.
+            let duplicate = match &item {
+                Item::Function(name, _) | Item::Alias(name, _) => {
+                    (module.functions.contains_key(name) || module.aliases.contains_key(name)).then(|| Path::snake(name.clone()))
+                }
+                Item::Signature(name, _) => module.signatures.contains_key(name).then(|| Path::snake(name.clone())),
+                Item::Import(name, import) => module.imports.contains_key(name).then(|| match import.path.case {
+                    ident::Case::Snake => Path::snake(name.clone()),
+                    ident::Case::Pascal => Path::pascal(name.clone()),
+                }),
+                Item::Glob(_) => None,
+                Item::Module(name, _) | Item::File(name, _) => {
+                    (module.modules.contains_key(name) || module.files.contains_key(name)).then(|| Path::snake(name.clone()))
+                }
+                Item::Type(name, _) => module.types.contains_key(name).then(|| Path::pascal(name.clone())),
+            };
+            if let Some(path) = duplicate {
+                return Err(ItemFromCharsPrefixError::Duplicate(path).at(start));
+            }
+
+            match item {
+                Item::Function(name, function) => {
+                    module.functions.insert(name, function);
+                }
+                Item::Signature(name, signature) => {
+                    module.signatures.insert(name, signature);
+                }
+                Item::Alias(name, import) => {
+                    module.aliases.insert(name, import);
+                }
+                Item::Import(name, import) => {
+                    module.imports.insert(name, import);
+                }
+                Item::Glob(glob) => {
+                    module.globs.push(glob);
+                }
+                Item::Module(name, sub) => {
+                    module.modules.insert(name, sub);
+                }
+                Item::File(name, visibility) => {
+                    module.files.insert(name, visibility);
+                }
+                Item::Type(name, type_) => {
+                    module.types.insert(name, type_);
+                }
+            }
         }
     }
 }
 
+/// Parses the `<A, B>` after a type's name, if there is one.
+fn type_parameters_from_chars_prefix(value: &[char]) -> Result<(Box<[Ident]>, &[char]), ItemError> {
+    type E = ItemFromCharsPrefixError;
+    match value.strip_prefix(&['<']) {
+        Some(value) => {
+            let parameter = |value| Ident::from_chars_pascal_case_prefix(value).ok_or(E::TypeParameterExpected);
+            let (parameters, value) = angle_list_from_chars_prefix(value, parameter, E::CloseAngleBracketExpected).map_err(|error| error.at(value))?;
+            Ok((parameters.into(), value))
+        },
+        None => Ok((Box::default(), value)),
+    }
+}
+
+/// Parses the fields of a `mul` or the variants of a `sum`; variants
+/// (`is_sum`) may leave out their type to carry `()`.
+fn type_fields_from_chars_prefix(value: &[char], is_sum: bool) -> Result<(HashMap<Ident, Type>, &[char]), ItemError> {
+    type E = ItemFromCharsPrefixError;
+    let mut value = value.strip_prefix(&['{']).ok_or_else(|| E::OpenCurlyBraceExpected.at(value))?;
+    let mut fields = HashMap::new();
+
+    loop {
+        value = strip_space(value);
+        if let Some(next_value) = value.strip_prefix(&['}']) {
+            break Ok((fields, next_value));
+        }
+
+        let (name, next_value) = Ident::from_chars_pascal_case_prefix(value).ok_or_else(|| E::TypeFieldNameExpected.at(value))?;
+        let next_value = strip_space(next_value);
+        let (type_, next_value) = match next_value.strip_prefix(&[':']) {
+            Some(next_value) => {
+                let next_value = strip_space(next_value);
+                Type::from_chars_prefix(next_value).map_err(|error| E::TypeField(error).at(next_value))?
+            },
+            None if is_sum => (Type::Tuple(Box::default()), next_value),
+            None => Err(E::TypeFieldColonExpected.at(next_value))?,
+        };
+        let next_value = strip_space(next_value);
+        value = next_value.strip_prefix(&[',']).unwrap_or(next_value);
+
+        fields.insert(name, type_);
+    }
+}
+
 pub struct Struct {
-    fields: HashMap<Ident, Type>
+    pub visibility: Visibility,
+    pub parameters: Box<[Ident]>,
+    pub fields: HashMap<Ident, Type>
+}
+
+pub struct Enum {
+    pub visibility: Visibility,
+    pub parameters: Box<[Ident]>,
+    pub variants: HashMap<Ident, Type>
+}
+
+/// A `type Name<A> = Type;` item, standing for `type_` wherever it is named.
+pub struct TypeAlias {
+    pub visibility: Visibility,
+    pub parameters: Box<[Ident]>,
+    pub type_: Type,
 }
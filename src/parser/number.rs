@@ -1,18 +1,17 @@
-
 pub struct Instance {
     first: Digit,
     others: Box<[Digit]>
 }
 
 impl Instance {
-    pub fn from_chars_prefix<'a>(value: &'a [char]) -> Option<(Self, &'a [char])> {
+    pub fn from_chars_prefix(value: &[char]) -> Option<(Self, &[char])> {
         let (&first, mut value) = value.split_first()?;
         let first = Digit::from_char(first)?;
         let mut others = Vec::new();
 
         while let Some((&item, next_value)) = value.split_first() {
-            value = next_value;
             if let Some(digit) = Digit::from_char(item) {
+                value = next_value;
                 others.push(digit);
             } else {
                 break;
@@ -24,8 +23,15 @@ impl Instance {
             others: others.into()
         }, value))
     }
+
+    /// The number the digits spell in base ten, if it fits in a `u64`.
+    pub fn value(&self) -> Option<u64> {
+        std::iter::once(&self.first).chain(self.others.iter())
+            .try_fold(0u64, |value, &digit| value.checked_mul(10)?.checked_add(digit as u64))
+    }
 }
 
+#[derive(Clone, Copy)]
 pub enum Digit {
     Zero,
     One, Two, Three,
@@ -1,43 +1,141 @@
+use std::fmt;
+use super::{normalize_path_base, strip_keyword, PathPart};
 use super::ident::{self, Instance as Ident};
 
-pub struct Instance {
+/// The module a path starts from: the one it is written in (also spelled
+/// `self::`), or the crate root, spelled `crate::`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Root {
+    Current,
+    Crate,
+}
+
+/// Everything before the last part of a path: where it starts, how many
+/// modules it climbs from there, and the modules it then descends into.
+#[derive(Clone, Debug)]
+pub struct Prefix {
+    pub root: Root,
+    pub supers: usize,
     pub parts: Box<[Ident]>,
-    pub name: Ident,
-    pub case: ident::Case,
 }
 
-impl Instance {
-    pub fn from_char_prefix<'a>(mut value: &'a [char]) -> Option<(Self, &'a [char])> {
+impl Prefix {
+    /// Parses a run of `part::` segments, leaving the text after the last `::`.
+    pub fn from_char_prefix(value: &[char]) -> (Self, &[char]) {
+        let (prefix, _, value) = Self::from_char_prefix_with_case(value);
+        (prefix, value)
+    }
+
+    fn from_char_prefix_with_case(mut value: &[char]) -> (Self, bool, &[char]) {
+        let mut root = Root::Current;
+        if let Some(next_value) = strip_keyword(value, &['c', 'r', 'a', 't', 'e']).and_then(|value| value.strip_prefix(&[':', ':'])) {
+            root = Root::Crate;
+            value = next_value;
+        } else if let Some(next_value) = strip_keyword(value, &['s', 'e', 'l', 'f']).and_then(|value| value.strip_prefix(&[':', ':'])) {
+            value = next_value;
+        }
+
         let mut parts = Vec::new();
-        let mut case = ident::Case::Snake;
+        let mut is_pascal = false;
 
         loop {
-            if let Some((part, next_value)) = Ident::from_chars_snake_case_prefix(value) {
+            if let Some(next_value) = strip_keyword(value, &['s', 'u', 'p', 'e', 'r']).and_then(|value| value.strip_prefix(&[':', ':'])) {
                 value = next_value;
-                parts.push(part);
+                parts.push(PathPart::Super);
 
-            } else if let Some((part, next_value)) = Ident::from_chars_pascal_case_prefix(value) {
+            } else if let Some((part, next_value)) = Ident::from_chars_snake_case_prefix(value) {
+                let Some(next_value) = next_value.strip_prefix(&[':', ':']) else { break };
                 value = next_value;
-                parts.push(part);
-                case = ident::Case::Pascal;
+                parts.push(PathPart::Module(part));
 
-            } else {
-                break;
-            }
-            
-            if let Some(next_value) = value.strip_prefix(&[':', ':']) {
+            } else if let Some((part, next_value)) = Ident::from_chars_pascal_case_prefix(value) {
+                let Some(next_value) = next_value.strip_prefix(&[':', ':']) else { break };
                 value = next_value;
+                parts.push(PathPart::Module(part));
+                is_pascal = true;
+
             } else {
                 break;
             }
         }
 
-        let name = parts.pop()?;
+        let (supers, parts) = normalize_path_base(parts);
+        (Self { root, supers, parts: parts.into() }, is_pascal, value)
+    }
+
+    /// Whether this prefix names the module it is written in.
+    pub fn is_empty(&self) -> bool {
+        self.root == Root::Current && self.supers == 0 && self.parts.is_empty()
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.root == Root::Crate {
+            write!(f, "crate::")?;
+        }
+        for _ in 0..self.supers {
+            write!(f, "super::")?;
+        }
+        for part in self.parts.iter() {
+            write!(f, "{part}::")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Instance {
+    pub prefix: Prefix,
+    pub name: Ident,
+    pub case: ident::Case,
+}
+
+impl Instance {
+    pub fn from_char_prefix(value: &[char]) -> Option<(Self, &[char])> {
+        let (prefix, is_pascal, value) = Prefix::from_char_prefix_with_case(value);
+
+        let (name, case, value) = if let Some((name, value)) = Ident::from_chars_snake_case_prefix(value) {
+            (name, ident::Case::Snake, value)
+        } else if let Some((name, value)) = Ident::from_chars_pascal_case_prefix(value) {
+            (name, ident::Case::Pascal, value)
+        } else {
+            None?
+        };
 
         Some((Self {
-            case,
+            case: if is_pascal { ident::Case::Pascal } else { case },
             name,
-            parts: parts.into(),
+            prefix,
         }, value))
     }
+
+    /// The path a plain pascal case `name` stands for in the module it is
+    /// written in.
+    pub fn pascal(name: Ident) -> Self {
+        Self {
+            prefix: Prefix { root: Root::Current, supers: 0, parts: Box::default() },
+            name,
+            case: ident::Case::Pascal,
+        }
+    }
+
+    /// The path a plain snake case `name` stands for in the module it is
+    /// written in.
+    pub fn snake(name: Ident) -> Self {
+        Self {
+            prefix: Prefix { root: Root::Current, supers: 0, parts: Box::default() },
+            name,
+            case: ident::Case::Snake,
+        }
+    }
+}
+
+impl fmt::Display for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.case {
+            ident::Case::Snake => write!(f, "{}{}", self.prefix, self.name),
+            ident::Case::Pascal => write!(f, "{}{}", self.prefix, self.name.to_pascal_case()),
+        }
+    }
 }
@@ -1,39 +1,1200 @@
+use std::{cell::{Cell, RefCell}, collections::HashMap, fmt};
+use super::parser::{self, Glob, Ident, Import, MatchSource, Path, Prefix, Root, Visibility};
 
-use std::collections::HashMap;
-use super::parser;
+pub struct Program {
+    pub functions: Vec<Function>,
+    pub entry: Option<usize>,
+    pub types: Vec<TypeItem>,
+    pub holes: usize,
+    pub nodes: usize,
+}
 
-pub struct Scope {
-    data: Box<[Value]>,
-    expression: Expression,
+/// `value` written out with the names `program` gives the ids in it.
+pub struct Named<'a, T> {
+    pub program: &'a Program,
+    pub value: &'a T,
 }
 
-pub enum Expression {
-    Match {
-        on: usize,
-        patterns: Box<[Pattern]>,
+impl Program {
+    pub fn named<'a, T>(&'a self, value: &'a T) -> Named<'a, T> {
+        Named { program: self, value }
+    }
+}
+
+pub struct Function {
+    pub name: Ident,
+    pub file: usize,
+    pub input: Type,
+    pub output: Type,
+    /// The input and output stated by a standalone `name :: In -> Out`.
+    pub signature: Option<(Type, Type)>,
+    pub locals: usize,
+    pub expression: Expression,
+}
 
+pub struct TypeItem {
+    pub name: Ident,
+    pub parameters: Box<[Ident]>,
+    pub kind: TypeItemKind,
+}
+
+pub enum TypeItemKind {
+    Struct(HashMap<Ident, Type>),
+    Enum(HashMap<Ident, Type>),
+    /// Every use of an alias is already expanded, so this is only kept for
+    /// diagnostics.
+    Alias(Type),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Type {
+    Item(usize, Box<[Type]>),
+    /// The type parameter at this index of the declaration it appears in.
+    Parameter(usize),
+    /// The alias declared by the item, applied to the arguments, and the type
+    /// it stands for. Kept so diagnostics can name the alias.
+    Alias(usize, Box<[Type]>, Box<Type>),
+    Tuple(Box<[Type]>),
+    Hole(usize),
+    Record(Box<[(Ident, Type)]>),
+    Variant(Box<[(Ident, Type)]>),
+    Function(Box<Type>, Box<Type>),
+    /// The numbers below this one.
+    Number(u64),
+}
+
+impl Type {
+    /// Whether the members of this type are named in pascal case, as those
+    /// of a `mul` or `sum` are, rather than in snake case.
+    pub fn is_declared(&self) -> bool {
+        matches!(self, Self::Item(..) | Self::Alias(..))
     }
 }
 
-pub enum Pattern {
-    Eq(Value),
-    Variant {
-        name: String,
-        value: Box<Pattern>,
-    },
-    Fields(HashMap<String, Pattern>),
+impl fmt::Display for Named<'_, Type> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |f: &mut fmt::Formatter<'_>, items: &[Type]| -> fmt::Result {
+            for (index, item) in items.iter().enumerate() {
+                if index != 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", self.program.named(item))?;
+            }
+            Ok(())
+        };
 
+        match self.value {
+            Type::Item(id, arguments) | Type::Alias(id, arguments, _) => {
+                write!(f, "{}", self.program.types[*id].name.to_pascal_case())?;
+                if !arguments.is_empty() {
+                    write!(f, "<")?;
+                    list(f, arguments)?;
+                    write!(f, ">")?;
+                }
+                Ok(())
+            },
+            // No declaration is at hand to name a parameter, so it goes by position.
+            Type::Parameter(index) => write!(f, "T{index}"),
+            Type::Tuple(items) => {
+                write!(f, "(")?;
+                list(f, items)?;
+                if items.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            },
+            Type::Hole(_) => write!(f, "?"),
+            Type::Number(number) => write!(f, "{number}"),
+            Type::Record(fields) if fields.is_empty() => write!(f, "{{}}"),
+            Type::Record(fields) => {
+                write!(f, "{{ ")?;
+                for (index, (name, type_)) in fields.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}: {}", self.program.named(type_))?;
+                }
+                write!(f, " }}")
+            },
+            Type::Variant(tags) => {
+                for (index, (tag, type_)) in tags.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "`{tag} {}", self.program.named(type_))?;
+                }
+                Ok(())
+            },
+            Type::Function(input, output) => match **input {
+                Type::Function(..) => write!(f, "({}) -> {}", self.program.named(&**input), self.program.named(&**output)),
+                _ => write!(f, "{} -> {}", self.program.named(&**input), self.program.named(&**output)),
+            },
+        }
+    }
 }
 
-pub enum Value {
+/// Sorts the fields of a record or the tags of a variant into the canonical
+/// order shared by structural equality and layout.
+pub fn canonical<T>(mut members: Vec<(Ident, T)>) -> Box<[(Ident, T)]> {
+    members.sort_by(|(left, _), (right, _)| left.cmp(right));
+    members.into()
+}
+
+pub enum Expression {
+    Mul {
+        id: usize,
+        type_: usize,
+        base: Option<Box<Expression>>,
+        fields: HashMap<Ident, Expression>,
+    },
     Sum {
         id: usize,
-        variant: String,
-        value: Box<Value>,
+        type_: usize,
+        tag: Ident,
+        body: Box<Expression>,
     },
-
-    Mul {
+    Call {
+        id: usize,
+        function: usize,
+        input: Box<Expression>,
+    },
+    Match {
         id: usize,
-        fields: HashMap<String, Value>,
+        on: Box<Expression>,
+        variants: Box<[(Pattern, Expression)]>,
+        source: MatchSource,
+    },
+    Member {
+        id: usize,
+        of: Box<Expression>,
+        name: Ident,
+    },
+    Variable {
+        id: usize,
+        local: usize,
+    },
+    Block {
+        statements: Box<[Statement]>,
+        result: Box<Expression>,
+    },
+    Debug {
+        expression: Box<Expression>,
+        from_end: usize,
+    },
+    Tuple(Box<[Expression]>),
+    Record(HashMap<Ident, Expression>),
+    Variant {
+        id: usize,
+        tag: Ident,
+        body: Box<Expression>,
+    },
+    /// A lambda, numbering its input among the locals of the enclosing
+    /// function, along with the outer locals its body refers to.
+    Lambda {
+        id: usize,
+        input: usize,
+        captures: Box<[usize]>,
+        body: Box<Expression>,
+    },
+    /// A call through a function value rather than a named function.
+    Apply {
+        id: usize,
+        function: Box<Expression>,
+        input: Box<Expression>,
+    },
+    /// A named function used as a value.
+    Function {
+        id: usize,
+        function: usize,
+    },
+    Number {
+        id: usize,
+        value: u64,
+    },
+}
+
+pub enum Pattern {
+    Equals(Expression),
+    /// `mul` is the `mul` called `tag` when one is in scope and `body` is
+    /// fields. The pattern then takes apart a value of that `mul` instead,
+    /// if that is what the checker finds the value to be.
+    Tag {
+        tag: Ident,
+        body: Box<Pattern>,
+        mul: Option<usize>,
+    },
+    Fields(HashMap<Ident, Pattern>),
+    Any(usize),
+    Wildcard,
+    Tuple(Box<[Pattern]>),
+    /// Alternatives binding the same locals.
+    Or(Box<[Pattern]>),
+    Bind(usize, Box<Pattern>),
+    Guard {
+        pattern: Box<Pattern>,
+        guard: Box<Pattern>,
+        on: Expression,
+    },
+}
+
+pub enum Statement {
+    Let {
+        local: usize,
+        value: Expression,
+    },
+    Expression(Expression),
+}
+
+#[derive(Debug)]
+pub enum Error {
+    UnknownModule {
+        name: Ident,
+        suggestion: Option<Ident>,
     },
+    UnknownFunction {
+        path: Box<Path>,
+        suggestion: Option<Ident>,
+    },
+    UnknownType {
+        path: Box<Path>,
+        suggestion: Option<Ident>,
+    },
+    UnknownVariable {
+        name: Ident,
+        suggestion: Option<Ident>,
+    },
+    AliasCycle(Ident),
+    ImportCycle(Path),
+    Ambiguous(Path),
+    Private(Path),
+    UnknownImport(Path),
+    AboveRoot(Prefix),
+    TypeArguments {
+        path: Path,
+        expected: usize,
+        found: usize,
+    },
+    TypeAliasCycle(Ident),
+    /// A value was built through an alias that does not name a `mul` or `sum`.
+    AliasConstructor(Ident),
+    SignatureWithoutBody(Ident),
+    /// An alternative of an or-pattern binds a name the first one does not,
+    /// or leaves out one it does.
+    OrBinding(Ident),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownModule { name, suggestion } => {
+                write!(f, "unknown module `{name}`")?;
+                did_you_mean(f, suggestion.as_ref().map(Ident::to_string))
+            },
+            Self::UnknownFunction { path, suggestion } => {
+                write!(f, "unknown function `{path}`")?;
+                did_you_mean(f, suggestion.as_ref().map(Ident::to_string))
+            },
+            Self::UnknownType { path, suggestion } => {
+                write!(f, "unknown type `{path}`")?;
+                did_you_mean(f, suggestion.as_ref().map(Ident::to_pascal_case))
+            },
+            Self::UnknownVariable { name, suggestion } => {
+                write!(f, "unknown variable `{name}`")?;
+                did_you_mean(f, suggestion.as_ref().map(Ident::to_string))
+            },
+            Self::AliasCycle(name) => write!(f, "function alias `{name}` refers back to itself"),
+            Self::ImportCycle(path) => write!(f, "import `{path}` refers back to itself"),
+            Self::Ambiguous(path) => write!(f, "`{path}` is brought in by more than one glob import"),
+            Self::Private(path) => write!(f, "`{path}` is private here"),
+            Self::UnknownImport(path) => write!(f, "`{path}` names nothing to import"),
+            Self::AboveRoot(prefix) => write!(f, "`{prefix}` climbs above the crate root"),
+            Self::TypeArguments { path, expected, found } => {
+                let plural = if *expected == 1 { "" } else { "s" };
+                write!(f, "`{path}` takes {expected} type argument{plural}, but got {found}")
+            },
+            Self::TypeAliasCycle(name) => write!(f, "type alias `{}` refers back to itself", name.to_pascal_case()),
+            Self::AliasConstructor(name) => {
+                write!(f, "values cannot be built through `{}`, as it names neither a mul nor a sum", name.to_pascal_case())
+            },
+            Self::SignatureWithoutBody(name) => write!(f, "signature `{name}` has no function to go with it"),
+            Self::OrBinding(name) => write!(f, "`{name}` is not bound by every alternative of an or-pattern"),
+        }
+    }
+}
+
+/// An error along with the file of the module it was found in.
+#[derive(Debug)]
+pub struct FileError {
+    pub file: usize,
+    pub error: Error,
+}
+
+fn did_you_mean(f: &mut fmt::Formatter<'_>, suggestion: Option<String>) -> fmt::Result {
+    match suggestion {
+        Some(suggestion) => write!(f, ", did you mean `{suggestion}`?"),
+        None => Ok(()),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Namespace {
+    Function,
+    Type,
+    Module,
+}
+
+impl Namespace {
+    /// `name` written in the case of the items of this namespace.
+    fn path(self, name: &Ident) -> Path {
+        match self {
+            Self::Type => Path::pascal(name.clone()),
+            Self::Function | Self::Module => Path::snake(name.clone()),
+        }
+    }
+}
+
+struct Module<'a> {
+    sup: Option<usize>,
+    visibility: Visibility,
+    file: usize,
+    functions: HashMap<Ident, usize>,
+    signatures: HashMap<&'a Ident, &'a parser::Signature>,
+    aliases: HashMap<&'a Ident, &'a Import>,
+    imports: HashMap<&'a Ident, &'a Import>,
+    globs: Vec<&'a Glob>,
+    types: HashMap<Ident, usize>,
+    modules: HashMap<Ident, usize>,
+}
+
+struct Env<'a> {
+    modules: Vec<Module<'a>>,
+    functions: Vec<(usize, &'a Ident, &'a parser::Function)>,
+    types: Vec<(usize, &'a Ident, &'a parser::TypeItem)>,
+    holes: Cell<usize>,
+    nodes: Cell<usize>,
+    /// The type aliases whose expansion is underway.
+    expanding: RefCell<Vec<usize>>,
+}
+
+impl<'a> Env<'a> {
+    fn collect(&mut self, sup: Option<usize>, value: &'a parser::Module) -> usize {
+        let id = self.modules.len();
+        self.modules.push(Module {
+            sup,
+            visibility: value.visibility,
+            file: value.file,
+            functions: HashMap::new(),
+            signatures: value.signatures.iter().collect(),
+            aliases: value.aliases.iter().collect(),
+            imports: value.imports.iter().collect(),
+            globs: value.globs.iter().collect(),
+            types: HashMap::new(),
+            modules: HashMap::new(),
+        });
+
+        for (name, function) in sorted(&value.functions) {
+            self.modules[id].functions.insert(name.clone(), self.functions.len());
+            self.functions.push((id, name, function));
+        }
+
+        for (name, type_) in sorted(&value.types) {
+            self.modules[id].types.insert(name.clone(), self.types.len());
+            self.types.push((id, name, type_));
+        }
+
+        for (name, module) in sorted(&value.modules) {
+            let sub = self.collect(Some(id), module);
+            self.modules[id].modules.insert(name.clone(), sub);
+        }
+
+        id
+    }
+
+    fn node(&self) -> usize {
+        let id = self.nodes.get();
+        self.nodes.set(id + 1);
+        id
+    }
+
+    /// Whether an item of `module` with `visibility` may be named from `from`.
+    fn visible(&self, module: usize, visibility: Visibility, from: usize) -> bool {
+        let scope = match visibility {
+            Visibility::Public => return true,
+            Visibility::Private => module,
+            Visibility::Super => self.modules[module].sup.unwrap_or(module),
+        };
+
+        let mut current = Some(from);
+        while let Some(id) = current {
+            if id == scope {
+                return true;
+            }
+            current = self.modules[id].sup;
+        }
+        false
+    }
+
+    /// The module `prefix` names when written in `module`.
+    fn prefix(&self, module: usize, prefix: &Prefix, from: usize, seen: &mut Vec<(usize, Ident)>) -> Result<usize, Error> {
+        let mut current = match prefix.root {
+            Root::Current => module,
+            Root::Crate => 0,
+        };
+        for _ in 0..prefix.supers {
+            current = self.modules[current].sup.ok_or_else(|| Error::AboveRoot(prefix.clone()))?;
+        }
+
+        for part in prefix.parts.iter() {
+            current = self.lookup(current, part, Namespace::Module, true, from, seen)?
+                .ok_or_else(|| Error::UnknownModule {
+                    name: part.clone(),
+                    suggestion: self.suggestion(current, part, Namespace::Module),
+                })?;
+        }
+        Ok(current)
+    }
+
+    fn path(&self, module: usize, path: &Path, namespace: Namespace, seen: &mut Vec<(usize, Ident)>) -> Result<Option<usize>, Error> {
+        let target = self.prefix(module, &path.prefix, module, seen)?;
+        self.lookup(target, &path.name, namespace, true, module, seen)
+    }
+
+    /// Finds `name` among the items of `module`, then its aliases and
+    /// imports, then, when `globs` is set, everything its glob imports bring
+    /// in, failing on anything `from` may not name.
+    fn lookup(&self, module: usize, name: &Ident, namespace: Namespace, globs: bool, from: usize, seen: &mut Vec<(usize, Ident)>) -> Result<Option<usize>, Error> {
+        let value = &self.modules[module];
+        let own = match namespace {
+            Namespace::Function => value.functions.get(name).map(|id| (*id, self.functions[*id].2.visibility)),
+            Namespace::Type => value.types.get(name).map(|id| (*id, self.types[*id].2.visibility())),
+            Namespace::Module => value.modules.get(name).map(|id| (*id, self.modules[*id].visibility)),
+        };
+        if let Some((id, visibility)) = own {
+            return match self.visible(module, visibility, from) {
+                true => Ok(Some(id)),
+                false => Err(Error::Private(namespace.path(name))),
+            };
+        }
+
+        let alias = match namespace {
+            Namespace::Function => value.aliases.get(name),
+            Namespace::Type | Namespace::Module => None,
+        };
+        let key = (module, name.clone());
+        if seen.contains(&key) {
+            return Err(match alias {
+                Some(_) => Error::AliasCycle(name.clone()),
+                None => Error::ImportCycle(namespace.path(name)),
+            });
+        }
+
+        if let Some(import) = alias.or(value.imports.get(name)) {
+            if !self.visible(module, import.visibility, from) {
+                return Err(Error::Private(namespace.path(name)));
+            }
+
+            let len = seen.len();
+            seen.push(key);
+            let found = self.path(module, &import.path, namespace, seen);
+            seen.truncate(len);
+
+            if let Some(id) = found? {
+                return Ok(Some(id));
+            }
+        }
+
+        match globs {
+            true => self.glob(module, name, namespace, from, seen),
+            false => Ok(None),
+        }
+    }
+
+    /// Finds `name` among the items that the glob imports of `module` make
+    /// visible to it, through the globs `from` may see.
+    fn glob(&self, module: usize, name: &Ident, namespace: Namespace, from: usize, seen: &mut Vec<(usize, Ident)>) -> Result<Option<usize>, Error> {
+        let mut found = None;
+        for glob in &self.modules[module].globs {
+            if !self.visible(module, glob.visibility, from) {
+                continue;
+            }
+            let target = self.prefix(module, &glob.prefix, module, seen)?;
+
+            let id = match self.lookup(target, name, namespace, false, module, seen) {
+                Err(Error::Private(_)) => None,
+                id => id?,
+            };
+            match (found, id) {
+                (Some(found), Some(id)) if found != id => return Err(Error::Ambiguous(namespace.path(name))),
+                (_, Some(id)) => found = Some(id),
+                (_, None) => {},
+            }
+        }
+        Ok(found)
+    }
+
+    /// Every name `module` can resolve in `namespace`, whether its own,
+    /// aliased, imported or brought in by a glob.
+    fn names(&self, module: usize, namespace: Namespace) -> Vec<&Ident> {
+        let value = &self.modules[module];
+        let mut names: Vec<&Ident> = match namespace {
+            Namespace::Function => value.functions.keys().chain(value.aliases.keys().copied()).collect(),
+            Namespace::Type => value.types.keys().collect(),
+            Namespace::Module => value.modules.keys().collect(),
+        };
+        names.extend(value.imports.keys().copied());
+
+        for glob in &value.globs {
+            if let Ok(target) = self.prefix(module, &glob.prefix, module, &mut Vec::new()) {
+                let target = &self.modules[target];
+                names.extend(match namespace {
+                    Namespace::Function => target.functions.keys().collect::<Vec<_>>(),
+                    Namespace::Type => target.types.keys().collect(),
+                    Namespace::Module => target.modules.keys().collect(),
+                });
+            }
+        }
+        names
+    }
+
+    fn suggestion(&self, module: usize, name: &Ident, namespace: Namespace) -> Option<Ident> {
+        suggestion(name, self.names(module, namespace))
+    }
+
+    /// The module a failed lookup of `path` ended up searching, if its prefix resolves.
+    fn searched(&self, module: usize, path: &Path) -> Option<usize> {
+        self.prefix(module, &path.prefix, module, &mut Vec::new()).ok()
+    }
+
+    fn function(&self, module: usize, path: &Path) -> Result<usize, Error> {
+        self.path(module, path, Namespace::Function, &mut Vec::new())?
+            .ok_or_else(|| Error::UnknownFunction {
+                path: Box::new(path.clone()),
+                suggestion: self.searched(module, path).and_then(|target| self.suggestion(target, &path.name, Namespace::Function)),
+            })
+    }
+
+    fn type_item(&self, module: usize, path: &Path) -> Result<usize, Error> {
+        self.path(module, path, Namespace::Type, &mut Vec::new())?
+            .ok_or_else(|| Error::UnknownType {
+                path: Box::new(path.clone()),
+                suggestion: self.searched(module, path).and_then(|target| self.suggestion(target, &path.name, Namespace::Type)),
+            })
+    }
+
+    /// The `mul` or `sum` that values built with `path` belong to, looking
+    /// through aliases of it.
+    fn constructor(&self, module: usize, path: &Path) -> Result<usize, Error> {
+        let id = self.type_item(module, path)?;
+        let (alias_module, name, parser::TypeItem::Alias(alias)) = self.types[id] else {
+            return Ok(id)
+        };
+        let parser::Type::Path(target, _) = &alias.type_ else {
+            return Err(Error::AliasConstructor(name.clone()))
+        };
+
+        if self.expanding.borrow().contains(&id) {
+            Err(Error::TypeAliasCycle(name.clone()))?
+        }
+        self.expanding.borrow_mut().push(id);
+        let target = self.constructor(alias_module, target);
+        self.expanding.borrow_mut().pop();
+        target
+    }
+
+    /// The `mul` called `name` that a pattern naming it in front of `body`
+    /// may take apart. Failing to find one is no error, as `name` may just
+    /// be a tag.
+    fn mul(&self, module: usize, name: &Ident, body: &parser::Pattern) -> Option<usize> {
+        if !matches!(body, parser::Pattern::Fields(_)) {
+            return None
+        }
+        let id = self.constructor(module, &Path::pascal(name.clone())).ok()?;
+        matches!(self.types[id].2, parser::TypeItem::Struct(_)).then_some(id)
+    }
+
+    /// Resolves `value` as written in `module`, inside a declaration taking `parameters`.
+    fn type_(&self, module: usize, value: &parser::Type, parameters: &[Ident]) -> Result<Type, Error> {
+        match value {
+            parser::Type::Path(path, arguments) => {
+                if path.prefix.is_empty() && arguments.is_empty() {
+                    if let Some(index) = parameters.iter().position(|parameter| *parameter == path.name) {
+                        return Ok(Type::Parameter(index));
+                    }
+                }
+
+                let id = self.type_item(module, path)?;
+                let expected = self.types[id].2.parameters().len();
+                if arguments.len() != expected {
+                    Err(Error::TypeArguments { path: path.clone(), expected, found: arguments.len() })?
+                }
+
+                let arguments: Box<[Type]> = arguments.iter()
+                    .map(|argument| self.type_(module, argument, parameters))
+                    .collect::<Result<_, Error>>()?;
+
+                match self.types[id] {
+                    (alias_module, name, parser::TypeItem::Alias(alias)) => {
+                        if self.expanding.borrow().contains(&id) {
+                            Err(Error::TypeAliasCycle(name.clone()))?
+                        }
+
+                        self.expanding.borrow_mut().push(id);
+                        let expansion = self.type_(alias_module, &alias.type_, &alias.parameters);
+                        self.expanding.borrow_mut().pop();
+
+                        let expansion = expansion?.instantiate(&arguments);
+                        Ok(Type::Alias(id, arguments, Box::new(expansion)))
+                    },
+                    _ => Ok(Type::Item(id, arguments)),
+                }
+            },
+            parser::Type::Tuple(items) => Ok(Type::Tuple(
+                items.iter()
+                    .map(|item| self.type_(module, item, parameters))
+                    .collect::<Result<_, Error>>()?
+            )),
+            parser::Type::Number(number) => Ok(Type::Number(*number)),
+            parser::Type::Hole => {
+                let hole = self.holes.get();
+                self.holes.set(hole + 1);
+                Ok(Type::Hole(hole))
+            },
+            parser::Type::Record(fields) => Ok(Type::Record(canonical(self.types(module, fields, parameters)?.into_iter().collect()))),
+            parser::Type::Variant(variants) => Ok(Type::Variant(canonical(self.types(module, variants, parameters)?.into_iter().collect()))),
+            parser::Type::Function(input, output) => Ok(Type::Function(
+                Box::new(self.type_(module, input, parameters)?),
+                Box::new(self.type_(module, output, parameters)?),
+            )),
+        }
+    }
+
+    fn types(&self, module: usize, value: &HashMap<Ident, parser::Type>, parameters: &[Ident]) -> Result<HashMap<Ident, Type>, Error> {
+        value.iter()
+            .map(|(name, type_)| Ok((name.clone(), self.type_(module, type_, parameters)?)))
+            .collect()
+    }
+}
+
+fn sorted<K: Ord, T>(value: &HashMap<K, T>) -> Vec<(&K, &T)> {
+    let mut value: Vec<_> = value.iter().collect();
+    value.sort_by_key(|&(name, _)| name);
+    value
+}
+
+/// Edit distance between two names, counted in characters of their snake case
+/// spelling, where swapping two neighbouring characters counts as one edit.
+fn distance(left: &[char], right: &[char]) -> usize {
+    let mut rows = vec![vec![0; right.len() + 1]; left.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=right.len()).collect();
+
+    for i in 1..=left.len() {
+        for j in 1..=right.len() {
+            let cost = usize::from(left[i - 1] != right[j - 1]);
+            let mut best = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+
+    rows[left.len()][right.len()]
+}
+
+/// The candidate closest to `name`, if any is close enough to be a likely typo.
+fn suggestion<'a>(name: &Ident, candidates: impl IntoIterator<Item = &'a Ident>) -> Option<Ident> {
+    let name_chars: Vec<char> = name.to_string().chars().collect();
+    let limit = (name_chars.len() / 3).max(1);
+
+    candidates.into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (distance(&name_chars, &candidate.to_string().chars().collect::<Vec<_>>()), candidate))
+        .filter(|&(distance, _)| distance <= limit)
+        .min_by_key(|&(distance, candidate)| (distance, candidate))
+        .map(|(_, candidate)| candidate.clone())
+}
+
+struct Locals {
+    names: Vec<(Ident, usize)>,
+    count: usize,
+    /// For each lambda being resolved, the first local it declares and the
+    /// outer locals it has referred to so far.
+    lambdas: Vec<(usize, Vec<usize>)>,
+    /// While resolving an alternative of an or-pattern other than the first,
+    /// the locals the first one bound, which the others bind again.
+    alternative: Option<Vec<(Ident, usize)>>,
+}
+
+impl Locals {
+    fn push(&mut self, name: Ident) -> usize {
+        let id = self.count;
+        self.count += 1;
+        self.names.push((name, id));
+        id
+    }
+
+    fn get(&mut self, name: &Ident) -> Result<usize, Error> {
+        let id = self.names.iter().rev()
+            .find(|(item, _)| item == name)
+            .map(|(_, id)| *id)
+            .ok_or_else(|| Error::UnknownVariable {
+                name: name.clone(),
+                suggestion: suggestion(name, self.names.iter().map(|(name, _)| name)),
+            })?;
+
+        for (start, captures) in &mut self.lambdas {
+            if id < *start && !captures.contains(&id) {
+                captures.push(id);
+            }
+        }
+        Ok(id)
+    }
+
+    /// Declares the local a pattern binds to `name`.
+    fn bind(&mut self, name: &Ident) -> Result<usize, Error> {
+        let Some(alternative) = &self.alternative else {
+            return Ok(self.push(name.clone()))
+        };
+
+        let (_, id) = alternative.iter()
+            .find(|(other, _)| other == name)
+            .ok_or_else(|| Error::OrBinding(name.clone()))?;
+        self.names.push((name.clone(), *id));
+        Ok(*id)
+    }
+
+    fn contains(&self, name: &Ident) -> bool {
+        self.names.iter().any(|(item, _)| item == name)
+    }
+}
+
+impl Env<'_> {
+    fn expression(&self, module: usize, locals: &mut Locals, value: &parser::Expression) -> Result<Expression, Error> {
+        Ok(match value {
+            parser::Expression::Mul { path, base, fields } => Expression::Mul {
+                id: self.node(),
+                type_: self.constructor(module, path)?,
+                base: match base {
+                    Some(base) => Some(Box::new(self.expression(module, locals, base)?)),
+                    None => None,
+                },
+                fields: fields.iter()
+                    .map(|(name, value)| Ok((name.clone(), self.expression(module, locals, value)?)))
+                    .collect::<Result<_, Error>>()?,
+            },
+
+            parser::Expression::Number(value) => Expression::Number { id: self.node(), value: *value },
+
+            parser::Expression::Sum { path, tag, body } => Expression::Sum {
+                id: self.node(),
+                type_: self.constructor(module, path)?,
+                tag: tag.clone(),
+                body: Box::new(self.expression(module, locals, body)?),
+            },
+
+            parser::Expression::Call { path, input } if path.prefix.is_empty() && locals.contains(&path.name) => Expression::Apply {
+                id: self.node(),
+                function: Box::new(Expression::Variable { id: self.node(), local: locals.get(&path.name)? }),
+                input: Box::new(self.expression(module, locals, input)?),
+            },
+
+            parser::Expression::Call { path, input } => Expression::Call {
+                id: self.node(),
+                function: self.function(module, path)?,
+                input: Box::new(self.expression(module, locals, input)?),
+            },
+
+            parser::Expression::Match { on, variants, source } => Expression::Match {
+                id: self.node(),
+                source: *source,
+                on: Box::new(self.expression(module, locals, on)?),
+                variants: variants.iter()
+                    .map(|(pattern, value)| {
+                        let scope = locals.names.len();
+                        let pattern = self.pattern(module, locals, pattern)?;
+                        let value = self.expression(module, locals, value)?;
+                        locals.names.truncate(scope);
+                        Ok((pattern, value))
+                    })
+                    .collect::<Result<_, Error>>()?,
+            },
+
+            parser::Expression::Member { of, name } => Expression::Member {
+                id: self.node(),
+                of: Box::new(self.expression(module, locals, of)?),
+                name: name.clone(),
+            },
+
+            parser::Expression::Variable { name } => match locals.get(name) {
+                Ok(local) => Expression::Variable { id: self.node(), local },
+                Err(e) => {
+                    match self.path(module, &Path::snake(name.clone()), Namespace::Function, &mut Vec::new()) {
+                        Ok(Some(function)) => Expression::Function { id: self.node(), function },
+                        _ => Err(e)?,
+                    }
+                },
+            },
+
+            parser::Expression::Block { statements, result } => {
+                let scope = locals.names.len();
+                let mut resolved = Vec::new();
+
+                for statement in statements.iter() {
+                    resolved.push(match statement {
+                        parser::Statement::Let { name, value } => {
+                            let value = self.expression(module, locals, value)?;
+                            Statement::Let { local: locals.push(name.clone()), value }
+                        },
+                        parser::Statement::Expression(value) => Statement::Expression(self.expression(module, locals, value)?),
+                    });
+                }
+
+                let result = self.expression(module, locals, result)?;
+                locals.names.truncate(scope);
+
+                Expression::Block {
+                    statements: resolved.into(),
+                    result: Box::new(result),
+                }
+            },
+
+            parser::Expression::Debug { expression, from_end } => Expression::Debug {
+                expression: Box::new(self.expression(module, locals, expression)?),
+                from_end: *from_end,
+            },
+
+            parser::Expression::Tuple(items) => Expression::Tuple(
+                items.iter()
+                    .map(|item| self.expression(module, locals, item))
+                    .collect::<Result<_, Error>>()?
+            ),
+
+            parser::Expression::Record(fields) => Expression::Record(
+                fields.iter()
+                    .map(|(name, value)| Ok((name.clone(), self.expression(module, locals, value)?)))
+                    .collect::<Result<_, Error>>()?
+            ),
+
+            parser::Expression::Variant { tag, body } => Expression::Variant {
+                id: self.node(),
+                tag: tag.clone(),
+                body: Box::new(self.expression(module, locals, body)?),
+            },
+
+            parser::Expression::Lambda { input, body } => {
+                let scope = locals.names.len();
+                locals.lambdas.push((locals.count, Vec::new()));
+                let input = locals.push(input.clone());
+                let body = self.expression(module, locals, body);
+                let (_, captures) = locals.lambdas.pop().unwrap();
+                locals.names.truncate(scope);
+
+                Expression::Lambda {
+                    id: self.node(),
+                    input,
+                    captures: captures.into(),
+                    body: Box::new(body?),
+                }
+            },
+        })
+    }
+
+    fn pattern(&self, module: usize, locals: &mut Locals, value: &parser::Pattern) -> Result<Pattern, Error> {
+        Ok(match value {
+            parser::Pattern::Equals(value) => Pattern::Equals(self.expression(module, locals, value)?),
+            parser::Pattern::Tag(tag, body) => Pattern::Tag {
+                tag: tag.clone(),
+                body: Box::new(self.pattern(module, locals, body)?),
+                mul: self.mul(module, tag, body),
+            },
+            parser::Pattern::Fields(fields) => Pattern::Fields(
+                fields.iter()
+                    .map(|(name, value)| Ok((name.clone(), self.pattern(module, locals, value)?)))
+                    .collect::<Result<_, Error>>()?
+            ),
+            parser::Pattern::Any(name) => Pattern::Any(locals.bind(name)?),
+            parser::Pattern::Wildcard => Pattern::Wildcard,
+            parser::Pattern::Tuple(items) => Pattern::Tuple(
+                items.iter()
+                    .map(|item| self.pattern(module, locals, item))
+                    .collect::<Result<_, Error>>()?
+            ),
+            parser::Pattern::Or(alternatives) => {
+                let scope = locals.names.len();
+                let mut resolved = vec![self.pattern(module, locals, &alternatives[0])?];
+                let bound = locals.names.split_off(scope);
+
+                for alternative in &alternatives[1..] {
+                    let outer = locals.alternative.replace(bound.clone());
+                    let alternative = self.pattern(module, locals, alternative);
+                    locals.alternative = outer;
+                    resolved.push(alternative?);
+
+                    let rebound = locals.names.split_off(scope);
+                    if let Some((name, _)) = bound.iter().find(|(name, _)| !rebound.iter().any(|(other, _)| other == name)) {
+                        Err(Error::OrBinding(name.clone()))?
+                    }
+                }
+
+                locals.names.extend(bound);
+                Pattern::Or(resolved.into())
+            },
+            parser::Pattern::Bind(name, pattern) => {
+                let local = locals.bind(name)?;
+                Pattern::Bind(local, Box::new(self.pattern(module, locals, pattern)?))
+            },
+            parser::Pattern::Guard { pattern, guard, on } => {
+                let pattern = self.pattern(module, locals, pattern)?;
+                let on = self.expression(module, locals, on)?;
+                Pattern::Guard {
+                    pattern: Box::new(pattern),
+                    guard: Box::new(self.pattern(module, locals, guard)?),
+                    on,
+                }
+            },
+        })
+    }
+}
+
+pub fn resolve(root: &parser::Module) -> Result<Program, FileError> {
+    let mut env = Env {
+        modules: Vec::new(),
+        functions: Vec::new(),
+        types: Vec::new(),
+        holes: Cell::new(0),
+        nodes: Cell::new(0),
+        expanding: RefCell::new(Vec::new()),
+    };
+    env.collect(None, root);
+
+    let in_file = |module: usize| {
+        let file = env.modules[module].file;
+        move |error| FileError { file, error }
+    };
+
+    for (id, module) in env.modules.iter().enumerate() {
+        env.module(id, module).map_err(in_file(id))?;
+    }
+
+    let types = env.types.iter()
+        .map(|&(module, name, value)| env.type_declaration(module, name, value).map_err(in_file(module)))
+        .collect::<Result<_, FileError>>()?;
+
+    let functions = env.functions.iter()
+        .map(|&(module, name, value)| env.function_definition(module, name, value).map_err(in_file(module)))
+        .collect::<Result<_, FileError>>()?;
+
+    let entry = Ident::from_chars_snake_case_prefix(&['s', 't', 'a', 'r', 't'])
+        .and_then(|(name, _)| env.modules[0].functions.get(&name).copied());
+
+    Ok(Program {
+        functions,
+        entry,
+        types,
+        holes: env.holes.get(),
+        nodes: env.nodes.get(),
+    })
+}
+
+impl<'a> Env<'a> {
+    /// Checks the items of the module `id` that are not resolved along with
+    /// a function or type: its signatures, aliases, imports and globs.
+    fn module(&self, id: usize, module: &Module<'a>) -> Result<(), Error> {
+        for (name, _) in sorted(&module.signatures) {
+            if !module.functions.contains_key(*name) {
+                Err(Error::SignatureWithoutBody((*name).clone()))?
+            }
+        }
+
+        for (name, alias) in sorted(&module.aliases) {
+            self.lookup(id, name, Namespace::Function, true, id, &mut Vec::new())?;
+            self.function(id, &alias.path)?;
+        }
+
+        for (_, import) in sorted(&module.imports) {
+            let (mut found, mut private) = (false, None);
+            for namespace in [Namespace::Function, Namespace::Type, Namespace::Module] {
+                match self.path(id, &import.path, namespace, &mut Vec::new()) {
+                    Ok(id) => found |= id.is_some(),
+                    Err(Error::Private(name)) => private = Some(name),
+                    Err(e) => Err(e)?,
+                }
+            }
+            if !found {
+                Err(private.map_or_else(|| Error::UnknownImport(import.path.clone()), Error::Private))?
+            }
+        }
+
+        for glob in &module.globs {
+            self.prefix(id, &glob.prefix, id, &mut Vec::new())?;
+        }
+
+        Ok(())
+    }
+
+    fn type_declaration(&self, module: usize, name: &Ident, value: &parser::TypeItem) -> Result<TypeItem, Error> {
+        Ok(TypeItem {
+            name: name.clone(),
+            parameters: value.parameters().into(),
+            kind: match value {
+                parser::TypeItem::Struct(value) => TypeItemKind::Struct(self.types(module, &value.fields, &value.parameters)?),
+                parser::TypeItem::Enum(value) => TypeItemKind::Enum(self.types(module, &value.variants, &value.parameters)?),
+                parser::TypeItem::Alias(value) => TypeItemKind::Alias(self.type_(module, &value.type_, &value.parameters)?),
+            },
+        })
+    }
+
+    fn function_definition(&self, module: usize, name: &Ident, value: &parser::Function) -> Result<Function, Error> {
+        let mut locals = Locals { names: Vec::new(), count: 0, lambdas: Vec::new(), alternative: None };
+        locals.push(value.input_name.clone());
+        let expression = self.expression(module, &mut locals, &value.expression)?;
+
+        Ok(Function {
+            name: name.clone(),
+            file: self.modules[module].file,
+            input: self.type_(module, &value.input, &[])?,
+            output: self.type_(module, &value.output, &[])?,
+            signature: match self.modules[module].signatures.get(name) {
+                Some(signature) => Some((self.type_(module, &signature.input, &[])?, self.type_(module, &signature.output, &[])?)),
+                None => None,
+            },
+            locals: locals.count,
+            expression,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::loader;
+
+    /// Resolves `source`, giving back its error as the driver prints it.
+    fn error(source: &str) -> String {
+        let (root, _) = loader::source("test.sn", source).expect("the source parses");
+        match super::resolve(&root) {
+            Ok(_) => panic!("expected a resolve error"),
+            Err(error) => error.error.to_string(),
+        }
+    }
+
+    /// Resolves `source`, which should have no errors.
+    fn resolves(source: &str) {
+        let (root, _) = loader::source("test.sn", source).expect("the source parses");
+        if let Err(error) = super::resolve(&root) {
+            panic!("{}", error.error);
+        }
+    }
+
+    const MODULES: &str = "
+        mod a { pub fn make () -> () x { () } fn hidden () -> () x { () } }
+        mod b { pub fn make () -> () x { () } }
+    ";
+
+    #[test]
+    fn imports() {
+        resolves(&format!("{MODULES} use a::make as build; fn start () -> () x {{ build () }}"));
+        resolves(&format!("{MODULES} use a::*; fn start () -> () x {{ make () }}"));
+        resolves(&format!("{MODULES} use a::*; use b::*; fn start () -> () x {{ () }}"));
+        assert_eq!(error(&format!("{MODULES} use a::nope; fn start () -> () x {{ () }}")), "`a::nope` names nothing to import");
+        assert_eq!(error("use b as c; use c as b; fn start () -> () x { c () }"), "import `c` refers back to itself");
+        assert_eq!(
+            error(&format!("{MODULES} use a::*; use b::*; fn start () -> () x {{ make () }}")),
+            "`make` is brought in by more than one glob import",
+        );
+    }
+
+    #[test]
+    fn visibility() {
+        assert_eq!(error(&format!("{MODULES} fn start () -> () x {{ a::hidden () }}")), "`hidden` is private here");
+        assert_eq!(error("mod a { sum S { X } } fn start () -> () x { let s = a::S:X (); () }"), "`S` is private here");
+        resolves("mod a { pub(super) fn make () -> () x { () } } fn start () -> () x { a::make () }");
+        assert_eq!(
+            error("mod o { pub mod a { pub(super) fn make () -> () x { () } } } fn start () -> () x { o::a::make () }"),
+            "`make` is private here",
+        );
+    }
+
+    #[test]
+    fn globs_skip_private_items() {
+        let source = format!("{MODULES} mod m {{ use super::a::*; pub fn f () -> () x {{ hidden () }} }} fn start () -> () x {{ () }}");
+        assert_eq!(error(&source), "unknown function `hidden`");
+        resolves(&format!("{MODULES} mod m {{ pub use super::a::*; }} fn start () -> () x {{ m::make () }}"));
+    }
+
+    #[test]
+    fn ids_in_name_order() {
+        let source = "
+            sum Zebra { Z } mul Apple { A: () }
+            fn zebra () -> () x { () } fn apple () -> () x { () } fn start () -> () x { () }
+            mod m { fn b () -> () x { () } fn a () -> () x { () } }
+        ";
+        let (root, _) = loader::source("test.sn", source).expect("the source parses");
+        let program = super::resolve(&root).unwrap_or_else(|error| panic!("{}", error.error));
+        let functions: Vec<String> = program.functions.iter().map(|function| function.name.to_string()).collect();
+        let types: Vec<String> = program.types.iter().map(|type_| type_.name.to_pascal_case()).collect();
+        assert_eq!(functions, ["apple", "start", "zebra", "a", "b"]);
+        assert_eq!(types, ["Apple", "Zebra"]);
+    }
+
+    #[test]
+    fn suggestions() {
+        let fruit = "mod fruit { pub fn ripe () -> () a { () } }";
+        assert_eq!(
+            error(&format!("{fruit} fn start () -> () a {{ fruit::rip () }}")),
+            "unknown function `fruit::rip`, did you mean `ripe`?",
+        );
+        assert_eq!(
+            error(&format!("{fruit} fn start () -> () a {{ friut::ripe () }}")),
+            "unknown module `friut`, did you mean `fruit`?",
+        );
+        assert_eq!(error("fn start () -> () value { valeu }"), "unknown variable `valeu`, did you mean `value`?");
+        assert_eq!(error("sum Color { Red } fn start () -> Colour a { () }"), "unknown type `Colour`, did you mean `Color`?");
+        assert_eq!(error("fn start () -> () a { zebra () }"), "unknown function `zebra`");
+    }
+
+    #[test]
+    fn type_argument_counts() {
+        let types = "sum Color { Red } sum Box<T> { B: T } mul Pair<L, R> { L: L, R: R }";
+        assert_eq!(error(&format!("{types} fn f () -> Color<()> a {{ () }}")), "`Color` takes 0 type arguments, but got 1");
+        assert_eq!(error(&format!("{types} fn f () -> Box a {{ () }}")), "`Box` takes 1 type argument, but got 0");
+        assert_eq!(error(&format!("{types} fn f () -> Pair<Color> a {{ () }}")), "`Pair` takes 2 type arguments, but got 1");
+    }
+
+    #[test]
+    fn path_prefixes() {
+        resolves("
+            fn top () -> () a { () }
+            mod outer {
+                pub fn here () -> () a { () }
+                pub mod inner {
+                    use super::*;
+                    use crate::top as t;
+                    pub fn go () -> () a {
+                        super::here (); crate::top (); self::leaf (); crate::outer::inner::super::here (); here (); t ()
+                    }
+                    fn leaf () -> () a { () }
+                }
+            }
+            fn start () -> () a { self::outer::inner::go () }
+        ");
+        resolves("fn start () -> () super { super }");
+        assert_eq!(error("fn start () -> () a { super::start () }"), "`super::` climbs above the crate root");
+        assert_eq!(error("fn start () -> () a { crate::super::start () }"), "`crate::super::` climbs above the crate root");
+    }
+
+    #[test]
+    fn signatures() {
+        resolves("f :: () -> () fn f () -> () a { () } fn start () -> () a { f () }");
+        assert_eq!(error("lonely :: () -> () fn start () -> () a { () }"), "signature `lonely` has no function to go with it");
+        assert_eq!(error("mod m { f :: () -> () } fn f () -> () a { () }"), "signature `f` has no function to go with it");
+    }
+
+    #[test]
+    fn type_alias_cycle() {
+        assert_eq!(error("type A = (B, ()); type B = A;"), "type alias `B` refers back to itself");
+        assert_eq!(error("type A = A;"), "type alias `A` refers back to itself");
+    }
 }
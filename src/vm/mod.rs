@@ -1,3 +1,7 @@
+use std::{fmt, io};
+
+pub const SCOPE_ID_LEN: usize = std::mem::size_of::<usize>();
+pub const POINTER_LEN: usize = std::mem::size_of::<usize>();
 
 fn copy_within_slice<T: Clone>(v: &mut [T], from: usize, to: usize, len: usize) {
     if from > to {
@@ -9,9 +13,9 @@ fn copy_within_slice<T: Clone>(v: &mut [T], from: usize, to: usize, len: usize)
     }
 }
 
-struct Scope {
-    data: Box<[u8]>,
-    actions: Vec<Action>,
+pub struct Scope {
+    pub data: Box<[u8]>,
+    pub actions: Vec<Action>,
 }
 
 pub enum Action {
@@ -21,6 +25,9 @@ pub enum Action {
         len: usize,
     },
     Call (usize),
+    CallIndirect {
+        from: usize,
+    },
     Match {
         left: usize,
         right: usize,
@@ -30,15 +37,186 @@ pub enum Action {
     },
     Debug {
         from: usize,
+        layout: Layout,
+        location: String,
+    },
+    /// Reserves `len` zeroed bytes on the heap and writes their address at `to`.
+    Allocate {
         len: usize,
+        to: usize,
     },
+    /// Copies `len` bytes from `from` into the heap at the address stored at `pointer`.
+    Store {
+        from: usize,
+        pointer: usize,
+        len: usize,
+    },
+    /// Copies `len` bytes from the heap at the address stored at `pointer` to `to`.
+    Load {
+        pointer: usize,
+        to: usize,
+        len: usize,
+    },
+}
+
+pub enum Layout {
+    Struct {
+        name: Option<String>,
+        fields: Box<[(String, Layout)]>,
+    },
+    Enum {
+        name: Option<String>,
+        variants: Box<[(String, Layout)]>,
+    },
+    Tuple(Box<[Layout]>),
+    /// A pointer to a heap value laid out as the layout at this index of the
+    /// table the VM was given.
+    Boxed(usize),
+    /// A closure: the scope to call, then a pointer to its captured values.
+    Function,
+    /// An unsigned number stored little-endian in this many bytes.
+    Number(usize),
 }
 
-pub struct Vm {
+impl Layout {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Boxed(_) => POINTER_LEN,
+            Self::Function => SCOPE_ID_LEN + POINTER_LEN,
+            Self::Number(len) => *len,
+            Self::Struct { fields, .. } => fields.iter().map(|(_, field)| field.len()).sum(),
+            Self::Enum { variants, .. } => 1 + variants.iter().map(|(_, variant)| variant.len()).max().unwrap_or(0),
+            Self::Tuple(items) => items.iter().map(Layout::len).sum(),
+        }
+    }
+
+    fn fmt_bytes(&self, bytes: &[u8], memory: &Memory, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Struct { name, fields } => {
+                if let Some(name) = name {
+                    write!(f, "{name} ")?;
+                }
+                if fields.is_empty() {
+                    return write!(f, "{{}}");
+                }
+
+                write!(f, "{{ ")?;
+                let mut offset = 0;
+                for (idx, (field_name, field)) in fields.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{field_name}: ")?;
+                    field.fmt_bytes(&bytes[offset..offset + field.len()], memory, f)?;
+                    offset += field.len();
+                }
+                write!(f, " }}")
+            },
+
+            Self::Enum { name, variants } => {
+                let (tag, variant) = &variants[bytes[0] as usize];
+                match name {
+                    Some(name) => write!(f, "{name}:{tag} ")?,
+                    None => write!(f, "`{tag} ")?,
+                }
+                variant.fmt_bytes(&bytes[1..1 + variant.len()], memory, f)
+            },
+
+            Self::Tuple(items) => {
+                write!(f, "(")?;
+                let mut offset = 0;
+                for (idx, item) in items.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, ", ")?;
+                    }
+                    item.fmt_bytes(&bytes[offset..offset + item.len()], memory, f)?;
+                    offset += item.len();
+                }
+                if items.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            },
+
+            Self::Boxed(index) => {
+                let layout = &memory.layouts[*index];
+                let at = read_pointer(bytes);
+                layout.fmt_bytes(&memory.heap[at..at + layout.len()], memory, f)
+            },
+
+            Self::Function => write!(f, "fn"),
+
+            Self::Number(_) => {
+                let mut number = [0; 8];
+                number[..bytes.len()].copy_from_slice(bytes);
+                write!(f, "{}", u64::from_le_bytes(number))
+            },
+        }
+    }
+}
+
+fn read_pointer(bytes: &[u8]) -> usize {
+    let mut pointer = [0; POINTER_LEN];
+    pointer.copy_from_slice(&bytes[..POINTER_LEN]);
+    usize::from_le_bytes(pointer)
+}
+
+/// What a value's bytes may point into.
+struct Memory<'a> {
+    heap: &'a [u8],
+    layouts: &'a [Layout],
+}
+
+struct Value<'a> {
+    layout: &'a Layout,
+    bytes: &'a [u8],
+    memory: Memory<'a>,
+}
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.layout.fmt_bytes(self.bytes, &self.memory, f)
+    }
+}
+
+/// Values live inline on `data`, except for those behind a pointer, which
+/// live on `heap`. Heap blocks are never freed. `dbg!` writes to `out`.
+pub struct Vm<W = io::Stdout> {
     data: Vec<u8>,
+    heap: Vec<u8>,
+    layouts: Box<[Layout]>,
+    out: W,
 }
 
 impl Vm {
+    /// A VM printing boxed values with `layouts`, the table `Layout::Boxed` indexes.
+    pub fn new(layouts: Box<[Layout]>) -> Self {
+        Vm::with_output(layouts, io::stdout())
+    }
+}
+
+impl<W: io::Write> Vm<W> {
+    /// Like `new`, but writing what `dbg!` prints to `out`.
+    pub fn with_output(layouts: Box<[Layout]>, out: W) -> Self {
+        Self {
+            data: Vec::new(),
+            heap: Vec::new(),
+            layouts,
+            out,
+        }
+    }
+
+    pub fn run(&mut self, id: usize, input: &[u8], output_len: usize, scopes: &[Scope]) -> Vec<u8> {
+        let start = self.data.len();
+        self.data.extend(input);
+        self.data.resize(start + input.len() + output_len, 0);
+        self.call(id, scopes);
+
+        let output = self.data.split_off(start + input.len());
+        self.data.truncate(start);
+        output
+    }
+
     pub fn call(&mut self, id: usize, scopes: &[Scope]) {
         let scope = &scopes[id];
         self.data.extend(scope.data.iter());
@@ -47,6 +225,13 @@ impl Vm {
             match action {
                 Action::Call(id) => self.call(*id, scopes),
 
+                Action::CallIndirect { from } => {
+                    let start = self.data.len() - from - SCOPE_ID_LEN;
+                    let mut id = [0; SCOPE_ID_LEN];
+                    id.copy_from_slice(&self.data[start .. start + SCOPE_ID_LEN]);
+                    self.call(usize::from_le_bytes(id), scopes)
+                },
+
                 Action::Copy { from, to, len } => {
                     let from = self.data.len() - from - len;
                     let to = self.data.len() - to;
@@ -64,11 +249,32 @@ impl Vm {
                     }
                 },
 
-                Action::Debug { from, len } => {
-                    println![
-                        "{:?}",
-                        &self.data[self.data.len() - from - len .. self.data.len() - from]
-                    ]
+                Action::Debug { from, layout, location } => {
+                    let value = Value {
+                        layout,
+                        bytes: &self.data[self.data.len() - from - layout.len() .. self.data.len() - from],
+                        memory: Memory { heap: &self.heap, layouts: &self.layouts },
+                    };
+                    writeln!(self.out, "{location}: {value}").expect("dbg! output is writable");
+                },
+
+                Action::Allocate { len, to } => {
+                    let at = self.heap.len();
+                    self.heap.resize(at + len, 0);
+                    let to = self.data.len() - to;
+                    self.data[to..to + POINTER_LEN].copy_from_slice(&at.to_le_bytes());
+                },
+
+                Action::Store { from, pointer, len } => {
+                    let from = self.data.len() - from - len;
+                    let at = read_pointer(&self.data[self.data.len() - pointer - POINTER_LEN..]);
+                    self.heap[at..at + len].copy_from_slice(&self.data[from..from + len]);
+                },
+
+                Action::Load { pointer, to, len } => {
+                    let at = read_pointer(&self.data[self.data.len() - pointer - POINTER_LEN..]);
+                    let to = self.data.len() - to;
+                    self.data[to..to + len].copy_from_slice(&self.heap[at..at + len]);
                 }
             }
         }